@@ -0,0 +1,277 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Rgb {
+	pub(crate) r: u8,
+	pub(crate) g: u8,
+	pub(crate) b: u8,
+}
+
+/// Amount of colors a terminal can display, ordered from the least to the most colors.
+/// Colors are mapped to the nearest available color, when the depth is lower than true color.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ColorDepth {
+	/// Only the 8 basic colors.
+	Colors8,
+	/// The 8 basic colors and their bright variants.
+	Colors16,
+	/// The xterm 256 color palette.
+	Colors256,
+	/// 24-bit RGB colors (default).
+	TrueColor,
+}
+
+impl ColorDepth {
+	/// The depth of a name in palettes, like '256' in '@depth 256'.
+	pub fn from_name(name: &str) -> Option<ColorDepth> {
+		Some(match name {
+			"truecolor" => ColorDepth::TrueColor,
+			"256" => ColorDepth::Colors256,
+			"16" => ColorDepth::Colors16,
+			"8" => ColorDepth::Colors8,
+			_ => return None,
+		})
+	}
+	
+	/// Name of the depth in palettes, like in '@depth 256'.
+	pub fn name(&self) -> &'static str {
+		match self {
+			ColorDepth::Colors8 => "8",
+			ColorDepth::Colors16 => "16",
+			ColorDepth::Colors256 => "256",
+			ColorDepth::TrueColor => "truecolor",
+		}
+	}
+}
+
+// The default colors of xterm, these vary between terminals, but are a good enough approximation.
+const BASIC_COLORS: [Rgb; 16] = [
+	Rgb::new(0, 0, 0),
+	Rgb::new(205, 0, 0),
+	Rgb::new(0, 205, 0),
+	Rgb::new(205, 205, 0),
+	Rgb::new(0, 0, 238),
+	Rgb::new(205, 0, 205),
+	Rgb::new(0, 205, 205),
+	Rgb::new(229, 229, 229),
+	Rgb::new(127, 127, 127),
+	Rgb::new(255, 0, 0),
+	Rgb::new(0, 255, 0),
+	Rgb::new(255, 255, 0),
+	Rgb::new(92, 92, 255),
+	Rgb::new(255, 0, 255),
+	Rgb::new(0, 255, 255),
+	Rgb::new(255, 255, 255),
+];
+
+// Channel values of the 6x6x6 color cube of the xterm 256 color palette (indices 16 to 231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl Rgb {
+	pub(crate) const fn new(r: u8, g: u8, b: u8) -> Rgb {
+		Rgb { r, g, b }
+	}
+	
+	/// SGR parameters, which set this color as foreground color.
+	pub(crate) fn foreground(&self, depth: ColorDepth) -> String {
+		match depth {
+			ColorDepth::TrueColor => format!("38;2;{};{};{}", self.r, self.g, self.b),
+			ColorDepth::Colors256 => format!("38;5;{}", self.nearest_256()),
+			ColorDepth::Colors16 | ColorDepth::Colors8 => match self.nearest_basic(depth == ColorDepth::Colors16) {
+				index @ 0..=7 => (30 + index).to_string(),
+				index => (90 + index - 8).to_string(),
+			},
+		}
+	}
+	
+	/// SGR parameters, which set this color as background color.
+	pub(crate) fn background(&self, depth: ColorDepth) -> String {
+		match depth {
+			ColorDepth::TrueColor => format!("48;2;{};{};{}", self.r, self.g, self.b),
+			ColorDepth::Colors256 => format!("48;5;{}", self.nearest_256()),
+			ColorDepth::Colors16 | ColorDepth::Colors8 => match self.nearest_basic(depth == ColorDepth::Colors16) {
+				index @ 0..=7 => (40 + index).to_string(),
+				index => (100 + index - 8).to_string(),
+			},
+		}
+	}
+	
+	/// Color of the xterm 256 color palette at the index: 16 basic colors, the 6x6x6 color cube and 24 grays.
+	pub(crate) fn from_index(index: u8) -> Rgb {
+		match index {
+			0..=15 => BASIC_COLORS[index as usize],
+			16..=231 => {
+				let offset = index - 16;
+				Rgb::new(
+					CUBE_LEVELS[(offset / 36) as usize],
+					CUBE_LEVELS[(offset / 6 % 6) as usize],
+					CUBE_LEVELS[(offset % 6) as usize],
+				)
+			}
+			_ => {
+				let level = 8 + (index - 232) * 10;
+				Rgb::new(level, level, level)
+			}
+		}
+	}
+	
+	/// Index of the perceptually nearest color of the xterm 256 color palette.
+	/// The first 16 colors are skipped, as terminals commonly change them with their theme.
+	fn nearest_256(&self) -> u8 {
+		self.nearest((16..=255).map(|index| (index, Rgb::from_index(index))))
+	}
+	
+	/// The color in CSS hex notation, like '#ff8000'.
+	pub(crate) fn hex(&self) -> String {
+		format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+	}
+	
+	/// Index of the perceptually nearest color of the 16 (or 8) basic colors.
+	fn nearest_basic(&self, bright: bool) -> u8 {
+		let count = if bright { 16 } else { 8 };
+		self.nearest(BASIC_COLORS.iter().take(count).enumerate().map(|(index, color)| (index as u8, *color)))
+	}
+	
+	fn nearest(&self, candidates: impl Iterator<Item = (u8, Rgb)>) -> u8 {
+		let target = self.oklab();
+		candidates
+			.map(|(index, color)| (index, distance(target, color.oklab())))
+			.min_by(|(_, a), (_, b)| a.total_cmp(b))
+			.map(|(index, _)| index)
+			.unwrap()
+	}
+	
+	/// Parses color functions like 'hsl(210, 80%, 60%)', 'hsv(210, 80%, 60%)' and 'oklch(0.7 0.15 250)'.
+	/// Arguments can be separated by commas and/or whitespaces. Returns None, if the text is not a color function.
+	pub(crate) fn parse_function(text: &str) -> Option<Result<Rgb, String>> {
+		let (name, arguments) = text.split_once('(')?;
+		let name = name.trim();
+		if !matches!(name, "hsl" | "hsv" | "oklch") {
+			return None;
+		}
+		Some(Self::parse_function_arguments(name, arguments).map_err(|message| format!("Could not parse color function '{text}': {message}")))
+	}
+	
+	fn parse_function_arguments(name: &str, arguments: &str) -> Result<Rgb, String> {
+		let arguments = arguments.trim_end().strip_suffix(')').ok_or("Missing closing ')'.")?;
+		// Rust tokens are printed with spaces in between ('80 %', '- 30'), hence glue signs and units to the numbers.
+		let mut glued = Vec::<String>::new();
+		for argument in arguments.split(|c: char| c == ',' || c.is_whitespace()).filter(|argument| !argument.is_empty()) {
+			match glued.last_mut() {
+				Some(previous) if argument == "%" || argument == "deg" || previous == "-" => previous.push_str(argument),
+				_ => glued.push(argument.to_string()),
+			}
+		}
+		let arguments = glued.iter().map(String::as_str).collect::<Vec<_>>();
+		let [first, second, third] = arguments[..] else {
+			return Err(format!("Expected 3 arguments, got {}.", arguments.len()));
+		};
+		Ok(match name {
+			"hsl" => Self::from_hsl(parse_hue(first)?, parse_fraction(second)?, parse_fraction(third)?),
+			"hsv" => Self::from_hsv(parse_hue(first)?, parse_fraction(second)?, parse_fraction(third)?),
+			_ => Self::from_oklch(parse_fraction(first)?, parse_number(second, 0.0, 0.5)?, parse_hue(third)?),
+		})
+	}
+	
+	/// Hue in degrees, saturation and lightness from 0 to 1.
+	fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Rgb {
+		let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+		Self::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+	}
+	
+	/// Hue in degrees, saturation and value from 0 to 1.
+	fn from_hsv(hue: f32, saturation: f32, value: f32) -> Rgb {
+		let chroma = value * saturation;
+		Self::from_hue_chroma(hue, chroma, value - chroma)
+	}
+	
+	fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Rgb {
+		let sector = hue.rem_euclid(360.0) / 60.0;
+		let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+		let (r, g, b) = match sector as u8 {
+			0 => (chroma, x, 0.0),
+			1 => (x, chroma, 0.0),
+			2 => (0.0, chroma, x),
+			3 => (0.0, x, chroma),
+			4 => (x, 0.0, chroma),
+			_ => (chroma, 0.0, x),
+		};
+		let channel = |value: f32| ((value + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+		Rgb::new(channel(r), channel(g), channel(b))
+	}
+	
+	/// Lightness from 0 to 1, chroma (usually up to 0.4) and hue in degrees.
+	pub(crate) fn from_oklch(lightness: f32, chroma: f32, hue: f32) -> Rgb {
+		let hue = hue.to_radians();
+		Self::from_oklab([lightness, chroma * hue.cos(), chroma * hue.sin()])
+	}
+	
+	/// Converts a color from the Oklab color space to sRGB, colors outside the sRGB gamut are clamped.
+	pub(crate) fn from_oklab([lightness, a, b]: [f32; 3]) -> Rgb {
+		let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+		let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+		let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+		
+		fn gamma(channel: f32) -> u8 {
+			let channel = if channel <= 0.003_130_8 {
+				channel * 12.92
+			} else {
+				1.055 * channel.powf(1.0 / 2.4) - 0.055
+			};
+			(channel * 255.0).round().clamp(0.0, 255.0) as u8
+		}
+		Rgb::new(
+			gamma(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+			gamma(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+			gamma(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+		)
+	}
+	
+	/// Converts the sRGB color into the perceptual Oklab color space.
+	/// See: https://bottosson.github.io/posts/oklab/
+	pub(crate) fn oklab(&self) -> [f32; 3] {
+		fn linear(channel: u8) -> f32 {
+			let channel = channel as f32 / 255.0;
+			if channel <= 0.04045 {
+				channel / 12.92
+			} else {
+				((channel + 0.055) / 1.055).powf(2.4)
+			}
+		}
+		let (r, g, b) = (linear(self.r), linear(self.g), linear(self.b));
+		
+		let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+		let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+		let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+		
+		[
+			0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+			1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+			0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+		]
+	}
+}
+
+/// Parses a number and ensures that it is within the range.
+fn parse_number(argument: &str, minimum: f32, maximum: f32) -> Result<f32, String> {
+	let number = argument.parse::<f32>().map_err(|_| format!("'{argument}' is not a number."))?;
+	if !(minimum..=maximum).contains(&number) {
+		return Err(format!("'{argument}' must be between {minimum} and {maximum}."));
+	}
+	Ok(number)
+}
+
+/// Parses a percentage ('80%') or fraction ('0.8') into a value from 0 to 1.
+fn parse_fraction(argument: &str) -> Result<f32, String> {
+	match argument.strip_suffix('%') {
+		Some(percentage) => parse_number(percentage, 0.0, 100.0).map(|percentage| percentage / 100.0),
+		None => parse_number(argument, 0.0, 1.0),
+	}
+}
+
+/// Parses a hue in degrees, optionally with 'deg' suffix.
+fn parse_hue(argument: &str) -> Result<f32, String> {
+	parse_number(argument.strip_suffix("deg").unwrap_or(argument), -360.0, 360.0)
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+	(a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
@@ -0,0 +1,41 @@
+use std::fmt::{Display, Formatter};
+
+/// Error of parsing a palette or a «color» code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+	message: String,
+	token: Option<usize>,
+}
+
+impl Error {
+	pub(crate) fn new(message: impl Into<String>) -> Error {
+		Error {
+			message: message.into(),
+			token: None,
+		}
+	}
+	
+	/// Marks the palette token (by index), which caused this error.
+	pub(crate) fn at_token(mut self, index: usize) -> Error {
+		self.token = Some(index);
+		self
+	}
+	
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+	
+	/// Index of the palette token which caused this error, if the error happened while parsing a palette.
+	/// Used by the proc macros to point at the offending token.
+	pub fn token(&self) -> Option<usize> {
+		self.token
+	}
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+impl std::error::Error for Error {}
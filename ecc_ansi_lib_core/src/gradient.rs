@@ -0,0 +1,126 @@
+use crate::color::Rgb;
+use crate::text::escape_sequence_length;
+use crate::width::char_width;
+use crate::Palette;
+
+/// Colors of a gradient, from the start (0) to the end (1) of the text.
+pub(crate) enum Gradient {
+	/// Colors spread evenly over the text, interpolated in the perceptual Oklab color space.
+	Stops(Vec<Rgb>),
+	/// All hues from red to violet, with the same perceived lightness.
+	Rainbow,
+}
+
+impl Gradient {
+	fn color_at(&self, position: f32) -> Rgb {
+		match self {
+			Gradient::Rainbow => Rgb::from_oklch(0.75, 0.16, 29.0 + position * 300.0),
+			Gradient::Stops(stops) => {
+				let scaled = position * (stops.len() - 1) as f32;
+				let index = (scaled.floor() as usize).min(stops.len() - 2);
+				let (from, to) = (stops[index].oklab(), stops[index + 1].oklab());
+				let fraction = scaled - index as f32;
+				Rgb::from_oklab([0, 1, 2].map(|channel| from[channel] + (to[channel] - from[channel]) * fraction))
+			}
+		}
+	}
+	
+	/// Colors every visible unit (character with its combining characters) of the text with the next color of the gradient.
+	/// Whitespaces, escape sequences and format arguments like '{}' are never split, whitespaces stay uncolored.
+	pub(crate) fn apply(&self, text: &str, palette: &Palette) -> String {
+		let units = split_units(text);
+		let colored_count = units.iter().filter(|unit| unit.colored).count();
+		
+		let mut output = String::with_capacity(text.len() * 20);
+		let mut previous_sequence = String::new();
+		let mut index = 0;
+		for unit in units {
+			if unit.colored {
+				let position = if colored_count > 1 { index as f32 / (colored_count - 1) as f32 } else { 0.0 };
+				index += 1;
+				// Skip repeated codes, which happen with short distances or reduced color depths.
+				let sequence = palette.sequence(&[palette.foreground(self.color_at(position))]);
+				if sequence != previous_sequence {
+					output.push_str(&sequence);
+					previous_sequence = sequence;
+				}
+			}
+			// The escaped color code opener is the only «» code allowed in gradients.
+			output.push_str(if unit.text == "««" { "«" } else { unit.text });
+		}
+		output
+	}
+}
+
+struct Unit<'a> {
+	text: &'a str,
+	colored: bool,
+}
+
+/// Splits the text into units, which are colored as a whole.
+/// Text of string literals is still escaped ('\n'), escaped escape sequences ('\u{1B}[m') are also kept together.
+fn split_units(text: &str) -> Vec<Unit<'_>> {
+	let mut units = Vec::new();
+	let mut rest = text;
+	while let Some(char) = rest.chars().next() {
+		let (length, colored) = match char {
+			'\u{1B}' | '\u{9B}' | '\u{9D}' => (escape_sequence_length(rest), false),
+			'\\' => {
+				let length = escape_length(rest);
+				if matches!(&rest[..length], "\\u{1B}" | "\\u{1b}" | "\\x1B" | "\\x1b") {
+					// Escaped ANSI escape sequence, measure it like the real escape sequence.
+					(length - 1 + escape_sequence_length(&format!("\u{1B}{}", &rest[length..])), false)
+				} else {
+					(length, !matches!(&rest[..length], "\\n" | "\\r" | "\\t"))
+				}
+			}
+			'«' if rest.starts_with("««") => (2 * '«'.len_utf8(), true),
+			'{' | '}' if rest[1..].starts_with(char) => (2, true),
+			'{' => (rest.find('}').map_or(rest.len(), |index| index + 1), true),
+			char if char.is_whitespace() => (char.len_utf8(), false),
+			_ => (cluster_length(rest), true),
+		};
+		units.push(Unit {
+			text: &rest[..length],
+			colored,
+		});
+		rest = &rest[length..];
+	}
+	units
+}
+
+/// Length of the escape in a string literal, like '\n', '\x41' or '\u{1F600}'.
+fn escape_length(text: &str) -> usize {
+	let mut chars = text.char_indices().skip(1);
+	match chars.next() {
+		Some((_, 'u')) => text.find('}').map_or(text.len(), |index| index + 1),
+		Some((index, 'x')) => (index + 3).min(text.len()),
+		Some((index, char)) => index + char.len_utf8(),
+		None => text.len(),
+	}
+}
+
+/// Length of the character including all following characters displayed together with it (approximated grapheme cluster).
+/// Covers combining characters, emoji joined by zero width joiners and flags.
+fn cluster_length(text: &str) -> usize {
+	let mut chars = text.char_indices().peekable();
+	let (_, first) = chars.next().unwrap();
+	let mut length = first.len_utf8();
+	let mut joined = false;
+	let mut open_flag = is_regional_indicator(first);
+	while let Some(&(index, char)) = chars.peek() {
+		let attached = joined || (!char.is_control() && char_width(char) == 0) || (open_flag && is_regional_indicator(char));
+		if !attached {
+			break;
+		}
+		open_flag = false;
+		joined = char == '\u{200D}';
+		length = index + char.len_utf8();
+		chars.next();
+	}
+	length
+}
+
+fn is_regional_indicator(char: char) -> bool {
+	('\u{1F1E6}'..='\u{1F1FF}').contains(&char)
+}
@@ -0,0 +1,85 @@
+use crate::styled::{parse_segments, Style};
+
+/// Converts text with ANSI escape sequences (like the output of `ansi!()`) to HTML, for example to publish terminal output.
+/// Colors and text attributes become `<span style="...">`, hyperlinks become `<a href="...">`.
+/// Only links to `http:`, `https:` and `mailto:` URLs become anchors, the text of other links (like `javascript:`) is kept without link.
+/// Adjacent text with the same style is merged into one span, other escape sequences are removed and the text is HTML escaped.
+/// Line breaks are kept as they are, thus the result is meant to be placed into a `<pre>` element.
+pub fn ansi_to_html(text: &str) -> String {
+	let (segments, _) = parse_segments(text);
+	let mut html = String::with_capacity(text.len());
+	let mut link = None;
+	for segment in &segments {
+		let segment_link = segment.style.link.as_deref().filter(|url| is_safe_url(url));
+		if segment_link != link {
+			if link.is_some() {
+				html.push_str("</a>");
+			}
+			if let Some(url) = segment_link {
+				html.push_str(&format!("<a href=\"{}\">", escape_html(url)));
+			}
+			link = segment_link;
+		}
+		let css = css(&segment.style);
+		if css.is_empty() {
+			html.push_str(&escape_html(&segment.text));
+		} else {
+			html.push_str(&format!("<span style=\"{css}\">{}</span>", escape_html(&segment.text)));
+		}
+	}
+	if link.is_some() {
+		html.push_str("</a>");
+	}
+	html
+}
+
+/// Whether the URL uses a scheme, which cannot run scripts when clicked (unlike 'javascript:' or 'data:').
+fn is_safe_url(url: &str) -> bool {
+	let Some((scheme, _)) = url.split_once(':') else {
+		return false;
+	};
+	["http", "https", "mailto"].iter().any(|safe| scheme.eq_ignore_ascii_case(safe))
+}
+
+/// CSS declarations of the style, empty for unstyled text.
+fn css(style: &Style) -> String {
+	let mut declarations = Vec::new();
+	let (foreground, background) = style.displayed_colors();
+	if let Some(color) = foreground {
+		declarations.push(format!("color:{}", color.hex()));
+	}
+	if let Some(color) = background {
+		declarations.push(format!("background-color:{}", color.hex()));
+	}
+	if style.bold {
+		declarations.push("font-weight:bold".to_string());
+	}
+	if style.dim {
+		declarations.push("opacity:0.5".to_string());
+	}
+	if style.italic {
+		declarations.push("font-style:italic".to_string());
+	}
+	match (style.underline, style.strikethrough) {
+		(true, true) => declarations.push("text-decoration:underline line-through".to_string()),
+		(true, false) => declarations.push("text-decoration:underline".to_string()),
+		(false, true) => declarations.push("text-decoration:line-through".to_string()),
+		(false, false) => {}
+	}
+	declarations.join(";")
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for char in text.chars() {
+		match char {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&#39;"),
+			_ => escaped.push(char),
+		}
+	}
+	escaped
+}
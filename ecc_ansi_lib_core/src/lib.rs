@@ -0,0 +1,23 @@
+// This crate contains everything that is shared by the proc macros and the runtime library.
+// Proc macro crates cannot export anything but macros, thus this is a separate crate.
+
+mod color;
+mod error;
+mod gradient;
+mod html;
+mod markup;
+mod palette;
+mod render;
+mod styled;
+mod svg;
+mod text;
+mod width;
+
+pub use color::ColorDepth;
+pub use error::Error;
+pub use html::ansi_to_html;
+pub use markup::ansi_to_markup;
+pub use palette::Palette;
+pub use render::render;
+pub use svg::{ansi_to_svg, SvgOptions};
+pub use text::{hyperlink, reduce_ansi, strip_ansi, visible_width};
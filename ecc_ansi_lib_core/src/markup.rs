@@ -0,0 +1,112 @@
+use crate::color::Rgb;
+use crate::palette::{is_attribute, Palette};
+use crate::styled::{parse_segments, Style};
+
+/// Converts text with ANSI escape sequences back into «» markup, to make colored strings readable (like in test failures).
+/// Colors are written as their palette key if the palette has that color, else as `R,G,B` (256 and 16 color sequences are converted to RGB).
+/// Each code only contains the changes to the previous style, like `«r»Red «b»bold«» plain`. Attributes and colors are disabled individually (`«/b»`, `«/fg»`).
+/// Links become `«link:url»` (with `»` percent-encoded) and `«/link»`, other escape sequences are removed. Rendering the markup with the palette results in the same look.
+pub fn ansi_to_markup(text: &str, palette: &Palette) -> String {
+	let (segments, end) = parse_segments(text);
+	let mut markup = String::with_capacity(text.len());
+	let mut previous = Style::default();
+	for (style, text) in segments.iter().map(|segment| (&segment.style, segment.text.as_str())).chain([(&end, "")]) {
+		push_changes(&mut markup, &previous, style, palette);
+		markup.push_str(&text.replace('«', "««"));
+		previous = style.clone();
+	}
+	markup
+}
+
+/// Appends the codes changing the style from the previous to the next one.
+fn push_changes(markup: &mut String, previous: &Style, next: &Style, palette: &Palette) {
+	if previous.link != next.link {
+		if previous.link.is_some() {
+			markup.push_str("«/link»");
+		}
+		if let Some(url) = &next.link {
+			// The code would end at '»', it is percent-encoded instead (which is the same URL).
+			markup.push_str(&format!("«link:{}»", url.replace('»', "%C2%BB")));
+		}
+	}
+	let unstyled = |style: &Style| Style { link: None, ..style.clone() } == Style::default();
+	if unstyled(next) {
+		if !unstyled(previous) {
+			markup.push_str("«»");
+		}
+		return;
+	}
+	
+	let mut items = Vec::new();
+	// Bold and dim are disabled together, the one which stays on is enabled again below.
+	let bold_or_dim_off = (previous.bold && !next.bold) || (previous.dim && !next.dim);
+	if bold_or_dim_off {
+		items.push(Item::Attribute("/b".to_string()));
+	}
+	let attributes = [
+		("b", previous.bold && !bold_or_dim_off, next.bold),
+		("d", previous.dim && !bold_or_dim_off, next.dim),
+		("i", previous.italic, next.italic),
+		("u", previous.underline, next.underline),
+		("rev", previous.reverse, next.reverse),
+		("strike", previous.strikethrough, next.strikethrough),
+	];
+	for (name, before, after) in attributes {
+		match (before, after) {
+			(true, false) if !matches!(name, "b" | "d") => items.push(Item::Attribute(format!("/{name}"))),
+			(false, true) => items.push(Item::Attribute(name.to_string())),
+			_ => {}
+		}
+	}
+	match (previous.foreground, next.foreground) {
+		(Some(_), None) => items.push(Item::Attribute("/fg".to_string())),
+		(before, Some(color)) if before != Some(color) => items.push(Item::Foreground(color)),
+		_ => {}
+	}
+	match (previous.background, next.background) {
+		(Some(_), None) => items.push(Item::Attribute("/bg".to_string())),
+		(before, Some(color)) if before != Some(color) => items.push(Item::Background(color)),
+		_ => {}
+	}
+	if items.is_empty() {
+		return;
+	}
+	
+	// Codes with a single item prefer colors over attributes, codes with multiple items prefer attributes (see `Palette::lookup()`).
+	let single = items.len() == 1;
+	let codes = items.iter().map(|item| match item {
+		Item::Attribute(name) if single => full_attribute_name(name).to_string(),
+		Item::Attribute(name) => name.clone(),
+		Item::Foreground(color) => match palette.color_name(*color) {
+			Some(key) if !single && is_attribute(key) => format!("fg:{key}"),
+			Some(key) => key.to_string(),
+			None => format!("{},{},{}", color.r, color.g, color.b),
+		},
+		Item::Background(color) => match palette.color_name(*color) {
+			Some(key) => format!("bg:{key}"),
+			None => format!("bg:{},{},{}", color.r, color.g, color.b),
+		},
+	}).collect::<Vec<_>>();
+	markup.push_str(&format!("«{}»", codes.join(",")));
+}
+
+/// A part of a «» code.
+enum Item {
+	/// Enables or disables (like '/b') an attribute, or disables a color ('/fg').
+	Attribute(String),
+	Foreground(Rgb),
+	Background(Rgb),
+}
+
+/// Full name of the short attribute name, single item codes would else prefer colors with that name ('b' is blue).
+fn full_attribute_name(name: &str) -> &str {
+	match name {
+		"b" => "bold",
+		"d" => "dim",
+		"i" => "italic",
+		"u" => "underline",
+		"rev" => "reverse",
+		"strike" => "strikethrough",
+		_ => name,
+	}
+}
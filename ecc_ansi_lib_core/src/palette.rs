@@ -0,0 +1,719 @@
+use crate::color::{ColorDepth, Rgb};
+use crate::gradient::Gradient;
+use crate::text::hyperlink_sequence;
+use crate::Error;
+use std::collections::HashMap;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+macro_rules! ansi_reset {
+	() => {
+		"\u{1B}[m"
+	};
+}
+
+/// The default palette, which is also used by `ansi!()`.
+const DEFAULT_PALETTE: &str = include_str!("default.palette");
+
+/// Wraps SGR parameters into a full ANSI escape sequence.
+fn sgr(parameters: &[String]) -> String {
+	format!("\u{1B}[{}m", parameters.join(";"))
+}
+
+/// Maps color keys to colors and resolves «color» codes to ANSI escape sequences.
+#[derive(Clone, Debug)]
+pub struct Palette {
+	palette: HashMap<String, Rgb>,
+	/// The palette with the colors of each theme applied.
+	themes: HashMap<String, HashMap<String, Rgb>>,
+	depth: ColorDepth,
+	plain: bool,
+}
+
+impl Default for Palette {
+	/// The default palette, same as used by `ansi!()`.
+	fn default() -> Self {
+		Self::parse(DEFAULT_PALETTE).expect("Default palette must be valid")
+	}
+}
+
+impl Palette {
+	/// Parses a palette from text, using the same format as the palette tokens of `ansi_extend!()`.
+	/// Use the `@default` directive to include the default palette.
+	pub fn parse(text: &str) -> Result<Palette, Error> {
+		Self::from_string_tokens(tokenize(text))
+	}
+	
+	/// Splits palette text into the tokens expected by `from_string_tokens()`.
+	pub fn tokenize(text: &str) -> Vec<String> {
+		tokenize(text)
+	}
+	
+	// TBI: Consider lazily evaluating the palette only when no other color input is available. Can save compilation time.
+	// TODO: Measure how long parsing the palette actually takes.
+	pub fn from_string_tokens(palette_tokens: Vec<String>) -> Result<Palette, Error> {
+		let mut variables = HashMap::new();
+		let mut palette = HashMap::new();
+		// Aliases ('warn = o') are resolved at the end, so that they follow later changes of their target:
+		let mut aliases: HashMap<String, (String, usize)> = HashMap::new();
+		// Theme sections replace colors, when the theme is active:
+		let mut themes: HashMap<String, Vec<ThemeEntry>> = HashMap::new();
+		let mut selected_theme = None;
+		let mut depth = ColorDepth::TrueColor;
+		let mut plain = false;
+		// Set by '@override', allows the next entry to replace an existing color:
+		let mut override_next = false;
+		
+		let mut tokens = Tokens::new(palette_tokens);
+		while let Some(key) = tokens.next() {
+			if key == "@" {
+				// Directive, which configures how the palette is applied:
+				let directive = tokens.next().ok_or_else(|| tokens.error("Got directive opener '@', but no directive name."))?;
+				match directive.as_str() {
+					// The default palette is parsed as if it was part of this palette:
+					"default" => tokens.insert(tokenize(DEFAULT_PALETTE)),
+					"plain" => plain = true,
+					"depth" => {
+						let value = tokens.next().ok_or_else(|| tokens.error(format!("Got directive '@{directive}', but no value token.")))?;
+						depth = ColorDepth::from_name(&value).ok_or_else(|| tokens.error(format!("Unknown color depth '{value}'. Expected one of: truecolor, 256, 16, 8")))?;
+					}
+					"override" => override_next = true,
+					"remove" => {
+						let key = tokens.next().ok_or_else(|| tokens.error(format!("Got directive '@{directive}', but no color to remove.")))?;
+						if palette.remove(&key).is_none() && aliases.remove(&key).is_none() {
+							let suggestion = suggest(&key, palette.keys().chain(aliases.keys())).map(|name| format!(" Did you mean '{name}'?")).unwrap_or_default();
+							return Err(tokens.error(format!("Cannot remove color '{key}', as it is not defined.{suggestion}")));
+						}
+					}
+					"theme" => {
+						let name = tokens.next().ok_or_else(|| tokens.error(format!("Got directive '@{directive}', but no theme name.")))?;
+						let section = tokens.next().filter(|section| section.starts_with('{') && section.ends_with('}'))
+							.ok_or_else(|| tokens.error(format!("Expected the colors of theme '{name}' in braces: '@theme {name} {{ ... }}'")))?;
+						let entries = Self::parse_theme(&section[1..(section.len() - 1)], &variables).map_err(|error| tokens.error(format!("In theme '{name}': {error}")))?;
+						themes.entry(name).or_default().extend(entries.into_iter().map(|(key, value)| ThemeEntry {
+							key,
+							value,
+							token: tokens.last_token(),
+						}));
+					}
+					"select" => {
+						selected_theme = Some(tokens.next().ok_or_else(|| tokens.error(format!("Got directive '@{directive}', but no theme name.")))?);
+					}
+					_ => return Err(tokens.error(format!("Unknown palette directive '@{directive}'. Expected one of: default, plain, depth, override, remove, theme, select"))),
+				}
+				if override_next && directive != "override" {
+					return Err(tokens.error("Expected a color after '@override'."));
+				}
+				continue;
+			}
+			
+			if !key.chars().all(|char| char == '_' || char.is_ascii_alphabetic()) {
+				return Err(tokens.error(format!("Variables/Color-Keys must only consist of ascii letters or underscore. Got '{key}'")));
+			}
+			
+			let next = tokens.peek().ok_or_else(|| tokens.error(format!("Got opening color-key/variable '{key}', but no values/assignment token.")))?;
+			if next == "=" {
+				tokens.next().unwrap(); // Yep is assignment, drop '='.
+				let value = tokens.next().ok_or_else(|| tokens.error("Got opening variable assignment, but no value token to assign."))?;
+				if value.starts_with(|char: char| char.is_ascii_digit()) {
+					// New variable:
+					let value = u8::from_str(&value).map_err(|e| tokens.error(format!("Could not parse unsigned byte value of variable assignment (variable '{key}'; value '{value}'). Error: {e}")))?;
+					variables.insert(key, value);
+					continue;
+				}
+				// New alias of another color:
+				Self::check_redefinition(&tokens, &key, &palette, &aliases, &mut override_next)?;
+				palette.remove(&key);
+				aliases.insert(key, (value, tokens.last_token()));
+			} else {
+				Self::check_redefinition(&tokens, &key, &palette, &aliases, &mut override_next)?;
+				let color = Self::parse_color_value(&mut tokens, &variables, &key)?;
+				aliases.remove(&key);
+				palette.insert(key, color);
+			}
+		}
+		if override_next {
+			return Err(tokens.error("Expected a color after '@override'."));
+		}
+		
+		// Resolve every theme, to report mistakes even if the theme is not used.
+		let themes = themes.iter()
+			.map(|(name, entries)| Ok((name.clone(), Self::resolve(&palette, &aliases, entries)?)))
+			.collect::<Result<HashMap<_, _>, Error>>()?;
+		// A selected theme, which is not defined is ignored. Palettes do not have to support every theme.
+		let palette = match selected_theme.and_then(|name| themes.get(&name)) {
+			Some(theme) => theme.clone(),
+			None => Self::resolve(&palette, &aliases, &[])?,
+		};
+		
+		Ok(Self {
+			palette,
+			themes,
+			depth,
+			plain,
+		})
+	}
+	
+	/// The palette in plain mode (like with '@plain'), which removes all color codes.
+	pub fn plain(&self) -> Palette {
+		let mut palette = self.clone();
+		palette.plain = true;
+		palette
+	}
+	
+	/// The palette with the colors of the theme (like "light"). If the palette has no such theme, it is returned as is.
+	pub fn with_theme(&self, name: &str) -> Palette {
+		let mut palette = self.clone();
+		if let Some(theme) = self.themes.get(name) {
+			palette.palette = theme.clone();
+		}
+		palette
+	}
+	
+	/// Key of the color in this palette, the shortest (then alphabetically first) one if several keys have the color.
+	pub(crate) fn color_name(&self, color: Rgb) -> Option<&str> {
+		self.palette.iter()
+			.filter(|(_, value)| **value == color)
+			.map(|(key, _)| key.as_str())
+			.min_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+	}
+	
+	/// Parses the colors of a theme section, which are either colors or aliases.
+	fn parse_theme(section: &str, variables: &HashMap<String, u8>) -> Result<Vec<(String, Entry)>, Error> {
+		let mut tokens = Tokens::new(tokenize(section));
+		let mut entries = Vec::new();
+		while let Some(key) = tokens.next() {
+			if tokens.peek().is_none() {
+				return Err(Error::new(format!("Got color-key '{key}', but no color.")));
+			}
+			if tokens.peek().is_some_and(|next| next == "=") {
+				tokens.next().unwrap();
+				let target = tokens.next().ok_or_else(|| Error::new(format!("Got alias '{key}', but no color to refer to.")))?;
+				entries.push((key, Entry::Alias(target)));
+			} else {
+				let color = Self::parse_color_value(&mut tokens, variables, &key)?;
+				entries.push((key, Entry::Color(color)));
+			}
+		}
+		Ok(entries)
+	}
+	
+	/// Applies the theme entries and resolves all aliases.
+	fn resolve(palette: &HashMap<String, Rgb>, aliases: &HashMap<String, (String, usize)>, theme: &[ThemeEntry]) -> Result<HashMap<String, Rgb>, Error> {
+		let (mut palette, mut aliases) = (palette.clone(), aliases.clone());
+		for entry in theme {
+			if !palette.contains_key(&entry.key) && !aliases.contains_key(&entry.key) {
+				return Err(Error::new(format!("Theme color '{}' must also be defined outside of the theme.", entry.key)).at_token(entry.token));
+			}
+			match &entry.value {
+				Entry::Color(color) => {
+					aliases.remove(&entry.key);
+					palette.insert(entry.key.clone(), *color);
+				}
+				Entry::Alias(target) => {
+					palette.remove(&entry.key);
+					aliases.insert(entry.key.clone(), (target.clone(), entry.token));
+				}
+			}
+		}
+		for key in aliases.keys() {
+			let color = Self::resolve_alias(key, &palette, &aliases)?;
+			palette.insert(key.clone(), color);
+		}
+		Ok(palette)
+	}
+	
+	/// Colors must not be defined twice by accident, replacing a color requires '@override' in front of it.
+	fn check_redefinition(tokens: &Tokens, key: &str, palette: &HashMap<String, Rgb>, aliases: &HashMap<String, (String, usize)>, override_next: &mut bool) -> Result<(), Error> {
+		let defined = palette.contains_key(key) || aliases.contains_key(key);
+		if defined && !*override_next {
+			return Err(tokens.error(format!("Color '{key}' is already defined, use '@override {key} ...' to replace it or '@remove {key}' to remove it.")));
+		}
+		if !defined && *override_next {
+			return Err(tokens.error(format!("Color '{key}' is not defined yet, thus it cannot be overridden. Remove the '@override'.")));
+		}
+		*override_next = false;
+		Ok(())
+	}
+	
+	/// Follows the alias (possibly over other aliases) to the color it refers to.
+	fn resolve_alias(key: &str, palette: &HashMap<String, Rgb>, aliases: &HashMap<String, (String, usize)>) -> Result<Rgb, Error> {
+		let mut current = key;
+		let mut visited = vec![key];
+		while let Some((target, token)) = aliases.get(current) {
+			if let Some(color) = palette.get(target) {
+				return Ok(*color);
+			}
+			if visited.contains(&target.as_str()) {
+				return Err(Error::new(format!("Alias '{key}' refers to itself (via '{}').", visited.join("' -> '"))).at_token(*token));
+			}
+			if !aliases.contains_key(target) {
+				let suggestion = suggest(target, palette.keys().chain(aliases.keys())).map(|name| format!(" Did you mean '{name}'?")).unwrap_or_default();
+				return Err(Error::new(format!("Alias '{current}' refers to unknown color '{target}'.{suggestion}")).at_token(*token));
+			}
+			visited.push(target);
+			current = target;
+		}
+		unreachable!("Aliases are only resolved for alias keys")
+	}
+	
+	fn parse_color_value(tokens: &mut Tokens, variables: &HashMap<String, u8>, key: &str) -> Result<Rgb, Error> {
+		// Color functions consist of the function name and the arguments in parentheses:
+		if tokens.peek_second().is_some_and(|arguments| arguments.starts_with('(')) {
+			let function = tokens.next().unwrap() + &tokens.next().unwrap();
+			return match Rgb::parse_function(&function) {
+				Some(result) => result.map_err(|message| tokens.error(format!("{message} For color '{key}'"))),
+				None => Err(tokens.error(format!("Unknown color function '{function}' for color '{key}'. Expected one of: hsl, hsv, oklch"))),
+			};
+		}
+		
+		let first_argument = tokens.peek().unwrap();
+		if first_argument.len() == 6 && !variables.contains_key(first_argument) {
+			// Argument has length of 6, thus it is not a byte.
+			// Argument is not a variable.
+			// Thus, it must be a hex color.
+			let first_argument = tokens.next().unwrap();
+			return Self::parse_hex(&first_argument)
+				.map_err(|e| tokens.error(format!("Could not parse hex input '{first_argument}' of color '{key}'. Error: {e}")));
+		}
+		
+		// Read 3 numbers/variables:
+		let r = Self::parse_color_channel(tokens, variables, "RED", key)?;
+		let g = Self::parse_color_channel(tokens, variables, "GREEN", key)?;
+		let b = Self::parse_color_channel(tokens, variables, "BLUE", key)?;
+		Ok(Rgb::new(r, g, b))
+	}
+	
+	fn parse_color_channel(tokens: &mut Tokens, variables: &HashMap<String, u8>, channel: &str, key: &str) -> Result<u8, Error> {
+		match tokens.next() {
+			None => Err(tokens.error(format!("Got color format, but no {channel} color channel. For color '{key}'"))),
+			Some(literal) => {
+				if let Some(b) = variables.get(&literal) {
+					Ok(*b)
+				} else {
+					u8::from_str(&literal).map_err(|e| {
+						let suggestion = suggest(&literal, variables.keys()).map(|name| format!(" Did you mean variable '{name}'?")).unwrap_or_default();
+						tokens.error(format!("Could not parse unsigned byte value '{literal}' of {channel} color channel (color '{key}'). Error: {e}.{suggestion}"))
+					})
+				}
+			}
+		}
+	}
+	
+	fn parse_hex(format: &str) -> Result<Rgb, ParseIntError> {
+		u32::from_str_radix(format, 16).map(|value| Rgb::new(
+			(value >> 16) as u8,
+			(value >> 8) as u8,
+			value as u8,
+		))
+	}
+	
+	/*
+		Currently supported:
+		- "" => Ansi reset
+		- A list of items, separated by commas and/or whitespaces. Each item is one of:
+		  - "<color>" => Foreground color
+		  - "fg:<color>" => Foreground color (never treated as attribute)
+		  - "bg:<color>" / "on <color>" => Background color
+		  - "<attribute>" => Enables an attribute (like "b" or "bold")
+		  - "/<attribute>" => Disables an attribute, without touching other attributes or colors
+		  - "/fg" / "/bg" => Resets the foreground/background color to the terminal default
+		Where <color> is one of:
+		- Lookup into the palette map
+		- 6-Character hex color codes
+		- "R, G, B" format for custom RGB values
+		- Color functions "hsl(210, 80%, 60%)", "hsv(210, 80%, 60%)" and "oklch(0.7 0.15 250)"
+		Some attribute short forms are also color keys of the default palette ("b" is blue).
+		If the code only consists of a single item, the color is preferred. Else the attribute is preferred.
+	 */
+	pub fn lookup(&self, mut format: &str) -> Result<String, Error> {
+		format = format.trim();
+		
+		// Empty => ANSI reset
+		if format.is_empty() {
+			return Ok(if self.plain { String::new() } else { ansi_reset!().to_string() });
+		}
+		
+		// The code is still validated in plain mode, to not hide mistakes when colors are disabled.
+		Ok(self.sequence(&self.parameters(format)?))
+	}
+	
+	/// Generates the escape sequence for the SGR parameters (see `parameters()`), or nothing in plain mode.
+	pub(crate) fn sequence(&self, parameters: &[String]) -> String {
+		if self.plain {
+			String::new()
+		} else {
+			sgr(parameters)
+		}
+	}
+	
+	/// Generates an escape sequence, which resets all styles and then applies the SGR parameters.
+	pub(crate) fn restore(&self, parameters: &[String]) -> String {
+		if parameters.is_empty() {
+			self.sequence(&[])
+		} else {
+			self.sequence(&[&["0".to_string()], parameters].concat())
+		}
+	}
+	
+	/// OSC 8 sequence starting a link to the URL, or ending the link if no URL is provided. Nothing in plain mode.
+	pub(crate) fn hyperlink(&self, url: Option<&str>) -> Result<String, Error> {
+		let url = url.unwrap_or_default();
+		if url.chars().any(|char| char.is_whitespace() || char.is_control()) {
+			return Err(Error::new(format!("Link URL must not contain whitespaces or control characters: '{url}'")));
+		}
+		Ok(if self.plain { String::new() } else { hyperlink_sequence(url) })
+	}
+	
+	/// SGR parameter of the color as foreground color, in the depth of this palette.
+	pub(crate) fn foreground(&self, color: Rgb) -> String {
+		color.foreground(self.depth)
+	}
+	
+	/// Parses the colors of a gradient code, like 'grad:r..y..b' (any amount of colors) or 'rainbow'.
+	/// Returns None, if the code is not a gradient.
+	pub(crate) fn gradient(&self, code: &str) -> Result<Option<Gradient>, Error> {
+		if code == "rainbow" {
+			return Ok(Some(Gradient::Rainbow));
+		}
+		let Some(stops) = code.strip_prefix("grad:") else {
+			return Ok(None);
+		};
+		let stops = stops.split("..").map(|stop| self.resolve_stop(stop.trim(), code)).collect::<Result<Vec<_>, _>>()?;
+		if stops.len() < 2 {
+			return Err(Error::new(format!("Expected at least two colors separated by '..' in gradient: '{code}'")));
+		}
+		Ok(Some(Gradient::Stops(stops)))
+	}
+	
+	fn resolve_stop(&self, stop: &str, code: &str) -> Result<Rgb, Error> {
+		let items = split_items(stop);
+		if let [first, following @ ..] = &items[..] {
+			if following.len() == 2 {
+				if let Some(rgb) = Self::parse_rgb_items(first, following) {
+					return Ok(rgb);
+				}
+			} else if following.is_empty() {
+				if let Some(rgb) = self.resolve_color(first)? {
+					return Ok(rgb);
+				}
+			}
+		}
+		let suggestion = suggest(stop, self.palette.keys()).map(|key| format!(" Did you mean '{key}'?")).unwrap_or_default();
+		Err(Error::new(format!("Could not parse gradient color: '{stop}' (in '{code}').{suggestion}")))
+	}
+	
+	/// Resolves the (trimmed, non-empty) «color» code to its SGR parameters, like "38;2;255;0;0". See `lookup()`.
+	pub(crate) fn parameters(&self, format: &str) -> Result<Vec<String>, Error> {
+		let items = split_items(format);
+		// A code with only one color (also in R,G,B format) prefers colors over attributes:
+		let prefer_color = items.len() == 1 || (items.len() == 3 && Self::parse_rgb_items(items[0], &items[1..]).is_some());
+		
+		let mut parameters = Vec::new();
+		let mut background_next = false;
+		let mut index = 0;
+		while index < items.len() {
+			let item = items[index];
+			index += 1;
+			
+			if item == "on" {
+				background_next = true;
+				continue;
+			}
+			if let Some(name) = item.strip_prefix('/') {
+				parameters.push(match name {
+					"fg" => "39".to_string(),
+					"bg" => "49".to_string(),
+					_ => Attribute::from_name(name)
+						.ok_or_else(|| Error::new(format!("Could not disable unknown attribute '{name}' in ANSI color format: '{format}'")))?
+						.disable_parameter()
+						.to_string(),
+				});
+				continue;
+			}
+			
+			let (item, background, forced_color) = if let Some(item) = item.strip_prefix("bg:") {
+				(item, true, true)
+			} else if let Some(item) = item.strip_prefix("fg:") {
+				(item, false, true)
+			} else {
+				(item, background_next, background_next)
+			};
+			background_next = false;
+			
+			if !forced_color && !prefer_color {
+				if let Some(attribute) = Attribute::from_name(item) {
+					parameters.push(attribute.enable_parameter().to_string());
+					continue;
+				}
+			}
+			
+			// R,G,B colors are split into three items, consume all of them:
+			let color = if let Some(rgb) = Self::parse_rgb_items(item, &items[index..]) {
+				index += 2;
+				rgb
+			} else if let Some(rgb) = self.resolve_color(item)? {
+				rgb
+			} else if let (false, Some(attribute)) = (forced_color, Attribute::from_name(item)) {
+				parameters.push(attribute.enable_parameter().to_string());
+				continue;
+			} else {
+				let suggestion = suggest(item, self.palette.keys()).map(|key| format!(" Did you mean '{key}'?")).unwrap_or_default();
+				return Err(Error::new(format!("Could not parse ANSI color format: '{item}' (in '{format}').{suggestion}")));
+			};
+			parameters.push(if background { color.background(self.depth) } else { color.foreground(self.depth) });
+		}
+		
+		if background_next {
+			return Err(Error::new(format!("Expected a background color after 'on' in ANSI color format: '{format}'")));
+		}
+		Ok(parameters)
+	}
+	
+	/// Attempts to parse the item and the two following items as R,G,B color.
+	fn parse_rgb_items(first: &str, following: &[&str]) -> Option<Rgb> {
+		if following.len() < 2 {
+			return None;
+		}
+		Some(Rgb::new(
+			u8::from_str(first).ok()?,
+			u8::from_str(following[0]).ok()?,
+			u8::from_str(following[1]).ok()?,
+		))
+	}
+	
+	fn resolve_color(&self, format: &str) -> Result<Option<Rgb>, Error> {
+		// Lookup in palette:
+		if let Some(v) = self.palette.get(format) {
+			return Ok(Some(*v));
+		}
+		
+		// Attempt to parse color functions (like HSL):
+		if let Some(result) = Rgb::parse_function(format) {
+			return result.map(Some).map_err(Error::new);
+		}
+		
+		//Attempt to parse RGB (as hex):
+		if format.len() == 6 {
+			return Ok(Self::parse_hex(format).ok());
+		}
+		
+		Ok(None)
+	}
+}
+
+/// Whether the item of a «» code is the name of a text attribute (like 'b' or 'bold').
+pub(crate) fn is_attribute(item: &str) -> bool {
+	Attribute::from_name(item).is_some()
+}
+
+#[derive(Clone, Copy)]
+enum Attribute {
+	Bold,
+	Dim,
+	Italic,
+	Underline,
+	Reverse,
+	Strikethrough,
+}
+
+impl Attribute {
+	fn from_name(name: &str) -> Option<Attribute> {
+		Some(match name {
+			"b" | "bold" => Attribute::Bold,
+			"d" | "dim" => Attribute::Dim,
+			"i" | "italic" => Attribute::Italic,
+			"u" | "underline" => Attribute::Underline,
+			"rev" | "reverse" => Attribute::Reverse,
+			"strike" | "strikethrough" => Attribute::Strikethrough,
+			_ => return None,
+		})
+	}
+	
+	fn enable_parameter(&self) -> u8 {
+		match self {
+			Attribute::Bold => 1,
+			Attribute::Dim => 2,
+			Attribute::Italic => 3,
+			Attribute::Underline => 4,
+			Attribute::Reverse => 7,
+			Attribute::Strikethrough => 9,
+		}
+	}
+	
+	/// Bold and dim share the same disable parameter, thus disabling one disables both.
+	fn disable_parameter(&self) -> u8 {
+		match self {
+			Attribute::Bold | Attribute::Dim => 22,
+			Attribute::Italic => 23,
+			Attribute::Underline => 24,
+			Attribute::Reverse => 27,
+			Attribute::Strikethrough => 29,
+		}
+	}
+}
+
+/// Value of a theme entry.
+enum Entry {
+	Color(Rgb),
+	Alias(String),
+}
+
+struct ThemeEntry {
+	key: String,
+	value: Entry,
+	/// Index of the theme section token.
+	token: usize,
+}
+
+/// Palette tokens, which remember the position of the last taken token for error reporting.
+struct Tokens {
+	tokens: Vec<String>,
+	/// Index of the provided token, which each token originates from. Inserted tokens originate from the token before them.
+	origins: Vec<usize>,
+	position: usize,
+}
+
+impl Tokens {
+	fn new(tokens: Vec<String>) -> Tokens {
+		Tokens {
+			origins: (0..tokens.len()).collect(),
+			tokens,
+			position: 0,
+		}
+	}
+	
+	/// Inserts tokens, which are taken next.
+	fn insert(&mut self, tokens: Vec<String>) {
+		let origin = self.last_token();
+		self.origins.splice(self.position..self.position, tokens.iter().map(|_| origin));
+		self.tokens.splice(self.position..self.position, tokens);
+	}
+	
+	/// Index of the provided token, which was taken last.
+	fn last_token(&self) -> usize {
+		self.origins.get(self.position.saturating_sub(1)).copied().unwrap_or_default()
+	}
+	
+	fn next(&mut self) -> Option<String> {
+		let token = self.tokens.get(self.position).cloned();
+		if token.is_some() {
+			self.position += 1;
+		}
+		token
+	}
+	
+	fn peek(&self) -> Option<&String> {
+		self.tokens.get(self.position)
+	}
+	
+	fn peek_second(&self) -> Option<&String> {
+		self.tokens.get(self.position + 1)
+	}
+	
+	/// Creates an error, which points at the last taken token.
+	fn error(&self, message: impl Into<String>) -> Error {
+		Error::new(message).at_token(self.last_token())
+	}
+}
+
+/// Finds the most similar name, if it is similar enough to be a typo.
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+	let maximum_distance = if name.chars().count() <= 3 { 1 } else { 2 };
+	candidates
+		.map(|candidate| (edit_distance(name, candidate), candidate))
+		.filter(|(distance, _)| *distance <= maximum_distance)
+		.min()
+		.map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut previous = (0..=b.len()).collect::<Vec<_>>();
+	for (i, char_a) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, char_b) in b.iter().enumerate() {
+			let substitution = previous[j] + if char_a == *char_b { 0 } else { 1 };
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
+/// Splits a «color» code into its items, separated by commas and/or whitespaces.
+/// Color functions like 'hsl(210, 80%, 60%)' stay a single item.
+fn split_items(format: &str) -> Vec<&str> {
+	let mut items = Vec::new();
+	let mut depth = 0;
+	let mut start = 0;
+	for (index, char) in format.char_indices() {
+		match char {
+			'(' => depth += 1,
+			')' => depth -= 1,
+			',' | ' ' | '\t' | '\n' if depth == 0 => {
+				items.push(&format[start..index]);
+				start = index + 1;
+			}
+			_ => {}
+		}
+	}
+	items.push(&format[start..]);
+	items.retain(|item| !item.trim().is_empty());
+	items.iter().map(|item| item.trim()).collect()
+}
+
+/// Splits palette text into the same tokens, which the Rust tokenizer would provide to `ansi_extend!()`.
+/// Line comments (`//`) are skipped.
+fn tokenize(text: &str) -> Vec<String> {
+	let mut tokens = Vec::new();
+	let mut current = String::new();
+	let mut iterator = text.chars().peekable();
+	while let Some(char) = iterator.next() {
+		if char == '(' || char == '{' {
+			// Parentheses/Braces and their content form a single token (like a group in Rust).
+			if !current.is_empty() {
+				tokens.push(std::mem::take(&mut current));
+			}
+			let closer = if char == '(' { ')' } else { '}' };
+			let mut group = String::from(char);
+			let mut depth = 1;
+			for next in iterator.by_ref() {
+				group.push(next);
+				if next == char {
+					depth += 1;
+				} else if next == closer {
+					depth -= 1;
+					if depth == 0 {
+						break;
+					}
+				}
+			}
+			tokens.push(group);
+			continue;
+		}
+		
+		let is_comment = char == '/' && iterator.peek() == Some(&'/');
+		if !is_comment && !char.is_whitespace() && char != '=' && char != '@' {
+			current.push(char);
+			continue;
+		}
+		
+		// Every other symbol terminates the current token:
+		if !current.is_empty() {
+			tokens.push(std::mem::take(&mut current));
+		}
+		if is_comment {
+			// Skip the rest of the line:
+			iterator.by_ref().find(|char| *char == '\n');
+		} else if !char.is_whitespace() {
+			// Punctuation is a token on its own:
+			tokens.push(char.to_string());
+		}
+	}
+	if !current.is_empty() {
+		tokens.push(current);
+	}
+	tokens
+}
@@ -0,0 +1,105 @@
+use crate::{Error, Palette};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Replaces all «color» codes in the text with ANSI escape sequences, using the same format as `ansi!()`.
+/// Unlike `ansi!()` the whole text is processed, not only string literals.
+///
+/// Styles can be stacked: `«+color»` remembers the active style before applying the color and `«-»` restores it.
+/// The stack is tracked per call (for `ansi!()` per string literal).
+///
+/// Links (OSC 8) make the text clickable in supporting terminals: `«link:https://example.com»Text«/link»`.
+///
+/// Gradients color each character of the text up to the closing code: `«grad:r..y..b»Text«/grad»` or `«rainbow»Text«/rainbow»`.
+pub fn render(format: &str, palette: &Palette) -> Result<String, Error> {
+	let mut output = String::with_capacity(format.len());
+	// SGR parameters applied since the last reset, and the ones remembered by '«+»':
+	let mut active = Vec::new();
+	let mut stack = Vec::new();
+	let mut iterator = format.chars().peekable();
+	
+	while let Some(char) = iterator.next() {
+		if char != '«' {
+			output.push(char);
+			continue;
+		}
+		
+		// Check if this is an escaped color code (starting with '««'), if so just add the opener.
+		if iterator.peek() == Some(&'«') {
+			iterator.next().unwrap(); // Consume the peeked symbol.
+			output.push('«');
+			continue;
+		}
+		
+		let mut color_format_buffer = String::new();
+		// Now collect all color format code characters (until it stops).
+		loop {
+			match iterator.next() {
+				Some('»') => break,
+				Some(char) => color_format_buffer.push(char),
+				None => return Err(Error::new(format!("Unterminated color format code ('«{color_format_buffer}'), expected a closing '»'."))),
+			}
+		}
+		// Resolve and append the ANSI color, while keeping track of the active style.
+		let code = color_format_buffer.trim();
+		if let Some(gradient) = palette.gradient(code)? {
+			let closer = if code == "rainbow" { "/rainbow" } else { "/grad" };
+			let text = collect_gradient_text(&mut iterator, closer)?;
+			output.push_str(&gradient.apply(&text, palette));
+			// Continue with the style from before the gradient:
+			output.push_str(&palette.restore(&active));
+		} else if let Some(url) = code.strip_prefix("link:") {
+			if url.trim().is_empty() {
+				return Err(Error::new(format!("Expected a URL in color format code '«{code}»'.")));
+			}
+			output.push_str(&palette.hyperlink(Some(url.trim()))?);
+		} else if code == "/link" {
+			output.push_str(&palette.hyperlink(None)?);
+		} else if code == "-" {
+			active = stack.pop().ok_or_else(|| Error::new(format!("Color format code '«-»' has no style to restore, use '«+color»' before (in '{format}').")))?;
+			output.push_str(&palette.restore(&active));
+		} else if let Some(code) = code.strip_prefix('+') {
+			stack.push(active.clone());
+			if !code.trim().is_empty() {
+				apply(palette, code.trim(), &mut active, &mut output)?;
+			}
+		} else if code.is_empty() {
+			active.clear();
+			output.push_str(&palette.lookup(code)?);
+		} else {
+			apply(palette, code, &mut active, &mut output)?;
+		}
+	}
+	
+	Ok(output)
+}
+
+/// Collects the text up to the closing code of a gradient. Other color codes are not allowed in gradients.
+fn collect_gradient_text(iterator: &mut Peekable<Chars>, closer: &str) -> Result<String, Error> {
+	let mut text = String::new();
+	while let Some(char) = iterator.next() {
+		if char != '«' {
+			text.push(char);
+			continue;
+		}
+		if iterator.peek() == Some(&'«') {
+			iterator.next().unwrap();
+			// Keep the escaped opener, so that it stays a single character of the gradient.
+			text.push_str("««");
+			continue;
+		}
+		let code = iterator.by_ref().take_while(|char| *char != '»').collect::<String>();
+		if code.trim() != closer {
+			return Err(Error::new(format!("Color format code '«{code}»' is not allowed inside of a gradient, expected the gradient to end with '«{closer}»'.")));
+		}
+		return Ok(text);
+	}
+	Err(Error::new(format!("Unterminated gradient, expected it to end with '«{closer}»'.")))
+}
+
+fn apply(palette: &Palette, code: &str, active: &mut Vec<String>, output: &mut String) -> Result<(), Error> {
+	let parameters = palette.parameters(code)?;
+	output.push_str(&palette.sequence(&parameters));
+	active.extend(parameters);
+	Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::color::Rgb;
+use crate::text::escape_sequence_length;
+
+/// Colors used for reversed text without explicit colors, like the defaults of a dark terminal.
+pub(crate) const DEFAULT_FOREGROUND: Rgb = Rgb::new(229, 229, 229);
+pub(crate) const DEFAULT_BACKGROUND: Rgb = Rgb::new(0, 0, 0);
+
+/// Colors, text attributes and link of text, as set by the escape sequences before it.
+/// Colors of the 256 and 16 color palettes are converted to RGB (using the xterm defaults).
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub(crate) struct Style {
+	pub(crate) foreground: Option<Rgb>,
+	pub(crate) background: Option<Rgb>,
+	pub(crate) bold: bool,
+	pub(crate) dim: bool,
+	pub(crate) italic: bool,
+	pub(crate) underline: bool,
+	pub(crate) reverse: bool,
+	pub(crate) strikethrough: bool,
+	/// URL of an OSC 8 hyperlink.
+	pub(crate) link: Option<String>,
+}
+
+impl Style {
+	/// The displayed foreground and background color, which are swapped for reversed text.
+	pub(crate) fn displayed_colors(&self) -> (Option<Rgb>, Option<Rgb>) {
+		if self.reverse {
+			(Some(self.background.unwrap_or(DEFAULT_BACKGROUND)), Some(self.foreground.unwrap_or(DEFAULT_FOREGROUND)))
+		} else {
+			(self.foreground, self.background)
+		}
+	}
+	
+	/// Applies the parameters of an SGR sequence (like '38;2;255;0;0'), unknown parameters are ignored.
+	fn apply_sgr(&mut self, parameters: &str) {
+		// Sub-parameters (like '38:2:255:0:0') are treated like parameters, empty parameters are a reset.
+		let parameters = parameters.split([';', ':']).map(|parameter| parameter.parse::<u16>().unwrap_or(0)).collect::<Vec<_>>();
+		let mut iterator = parameters.iter().copied();
+		while let Some(parameter) = iterator.next() {
+			match parameter {
+				0 => *self = Style { link: self.link.take(), ..Style::default() },
+				1 => self.bold = true,
+				2 => self.dim = true,
+				3 => self.italic = true,
+				4 => self.underline = true,
+				7 => self.reverse = true,
+				9 => self.strikethrough = true,
+				22 => (self.bold, self.dim) = (false, false),
+				23 => self.italic = false,
+				24 => self.underline = false,
+				27 => self.reverse = false,
+				29 => self.strikethrough = false,
+				30..=37 => self.foreground = Some(Rgb::from_index((parameter - 30) as u8)),
+				90..=97 => self.foreground = Some(Rgb::from_index((parameter - 90 + 8) as u8)),
+				39 => self.foreground = None,
+				40..=47 => self.background = Some(Rgb::from_index((parameter - 40) as u8)),
+				100..=107 => self.background = Some(Rgb::from_index((parameter - 100 + 8) as u8)),
+				49 => self.background = None,
+				38 | 48 => {
+					let mut channel = || iterator.next().map(|value| value.min(255) as u8);
+					let color = match channel() {
+						Some(5) => channel().map(Rgb::from_index),
+						Some(2) => match (channel(), channel(), channel()) {
+							(Some(r), Some(g), Some(b)) => Some(Rgb::new(r, g, b)),
+							_ => None,
+						},
+						_ => None,
+					};
+					if parameter == 38 {
+						self.foreground = color;
+					} else {
+						self.background = color;
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+}
+
+/// A part of the text, which is printed in the same style.
+#[derive(Debug)]
+pub(crate) struct Segment {
+	pub(crate) style: Style,
+	pub(crate) text: String,
+}
+
+/// Splits text with ANSI escape sequences into segments of the same style, without the escape sequences.
+/// SGR sequences (colors and attributes) and OSC 8 hyperlinks change the style, all other escape sequences are removed.
+/// Adjacent text with the same style is merged into one segment, escape sequences without text in between produce no segment.
+/// Also returns the style at the end of the text, as changed by escape sequences after the last segment.
+pub(crate) fn parse_segments(text: &str) -> (Vec<Segment>, Style) {
+	let mut segments = Vec::<Segment>::new();
+	let mut style = Style::default();
+	let mut rest = text;
+	while !rest.is_empty() {
+		let start = rest.find(['\u{1B}', '\u{9B}', '\u{9D}']).unwrap_or(rest.len());
+		if start > 0 {
+			match segments.last_mut() {
+				Some(segment) if segment.style == style => segment.text.push_str(&rest[..start]),
+				_ => segments.push(Segment { style: style.clone(), text: rest[..start].to_string() }),
+			}
+		}
+		rest = &rest[start..];
+		let length = escape_sequence_length(rest);
+		apply_sequence(&mut style, &rest[..length]);
+		rest = &rest[length..];
+	}
+	(segments, style)
+}
+
+fn apply_sequence(style: &mut Style, sequence: &str) {
+	if let Some(parameters) = sequence.strip_prefix("\u{1B}[").or_else(|| sequence.strip_prefix('\u{9B}')) {
+		if let Some(parameters) = parameters.strip_suffix('m') {
+			style.apply_sgr(parameters);
+		}
+	} else if let Some(command) = sequence.strip_prefix("\u{1B}]").or_else(|| sequence.strip_prefix('\u{9D}')) {
+		// Hyperlinks: '8;parameters;url', terminated by BEL or the string terminator. An empty URL ends the link.
+		let command = command.trim_end_matches(['\u{07}', '\u{9C}']);
+		let command = command.strip_suffix("\u{1B}\\").unwrap_or(command);
+		if let Some((_, url)) = command.strip_prefix("8;").and_then(|link| link.split_once(';')) {
+			style.link = (!url.is_empty()).then(|| url.to_string());
+		}
+	}
+}
@@ -0,0 +1,151 @@
+use crate::html::escape_html;
+use crate::styled::{parse_segments, Style, DEFAULT_BACKGROUND, DEFAULT_FOREGROUND};
+use crate::width::char_width;
+
+/// Settings of `ansi_to_svg()`.
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+	/// CSS color behind the text.
+	pub background: String,
+	/// Draws a terminal window frame with a title bar around the text.
+	pub window: bool,
+	/// Title in the title bar of the window frame.
+	pub title: String,
+	/// Font size in pixels, grid cells are 0.6 times as wide and 1.2 times as high.
+	pub font_size: f32,
+	/// The font, should be a monospace font.
+	pub font_family: String,
+}
+
+impl Default for SvgOptions {
+	fn default() -> Self {
+		SvgOptions {
+			background: DEFAULT_BACKGROUND.hex(),
+			window: false,
+			title: String::new(),
+			font_size: 14.0,
+			font_family: "ui-monospace, Menlo, Consolas, 'DejaVu Sans Mono', monospace".to_string(),
+		}
+	}
+}
+
+/// Text of one style in a line, starting at a column of the grid.
+struct Run<'a> {
+	style: &'a Style,
+	text: String,
+	column: usize,
+	width: usize,
+}
+
+/// Renders text with ANSI escape sequences (like the output of `ansi!()`) as standalone SVG image, which looks like a terminal.
+/// Every character is placed on a monospace grid, wide characters take two cells and tabs advance to the next multiple of 8 columns.
+/// Colors and text attributes are drawn like `ansi_to_html()` does, links and other escape sequences are removed.
+pub fn ansi_to_svg(text: &str, options: &SvgOptions) -> String {
+	let (segments, _) = parse_segments(text);
+	let mut lines = vec![Vec::<Run>::new()];
+	let mut column = 0;
+	for segment in &segments {
+		for (index, part) in segment.text.split('\n').enumerate() {
+			if index > 0 {
+				lines.push(Vec::new());
+				column = 0;
+			}
+			let mut run = Run { style: &segment.style, text: String::new(), column, width: 0 };
+			for char in part.chars() {
+				match char {
+					'\t' => {
+						let spaces = 8 - column % 8;
+						run.text.extend(std::iter::repeat_n(' ', spaces));
+						column += spaces;
+					}
+					'\r' => {}
+					_ => {
+						run.text.push(char);
+						column += char_width(char);
+					}
+				}
+			}
+			run.width = column - run.column;
+			if !run.text.is_empty() {
+				lines.last_mut().unwrap().push(run);
+			}
+		}
+	}
+	// A final line break does not start another line.
+	if text.ends_with('\n') && lines.len() > 1 {
+		lines.pop();
+	}
+	
+	let cell_width = options.font_size * 0.6;
+	let line_height = options.font_size * 1.2;
+	let padding = options.font_size;
+	let title_height = if options.window { options.font_size * 2.0 } else { 0.0 };
+	let columns = lines.iter().filter_map(|runs| runs.last().map(|run| run.column + run.width)).max().unwrap_or(0);
+	let width = columns as f32 * cell_width + padding * 2.0;
+	let height = lines.len() as f32 * line_height + padding * 2.0 + title_height;
+	
+	let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n", number(width), number(height));
+	let corner = if options.window { " rx=\"8\"" } else { "" };
+	svg.push_str(&format!("<rect width=\"100%\" height=\"100%\"{corner} fill=\"{}\"/>\n", escape_html(&options.background)));
+	if options.window {
+		let radius = options.font_size * 0.4;
+		for (index, color) in ["#ff5f56", "#ffbd2e", "#27c93f"].iter().enumerate() {
+			let x = padding + radius + index as f32 * radius * 3.0;
+			svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{color}\"/>\n", number(x), number(title_height / 2.0), number(radius)));
+		}
+		if !options.title.is_empty() {
+			svg.push_str(&format!(
+				"<text x=\"50%\" y=\"{}\" text-anchor=\"middle\" font-family=\"{}\" font-size=\"{}\" fill=\"#8c8c8c\">{}</text>\n",
+				number(title_height / 2.0 + options.font_size * 0.35), escape_html(&options.font_family), number(options.font_size), escape_html(&options.title),
+			));
+		}
+	}
+	
+	svg.push_str(&format!(
+		"<g font-family=\"{}\" font-size=\"{}\" fill=\"{}\" xml:space=\"preserve\">\n",
+		escape_html(&options.font_family), number(options.font_size), DEFAULT_FOREGROUND.hex(),
+	));
+	for (line, runs) in lines.iter().enumerate() {
+		let top = padding + title_height + line as f32 * line_height;
+		for run in runs {
+			let x = padding + run.column as f32 * cell_width;
+			let width = run.width as f32 * cell_width;
+			let (foreground, background) = run.style.displayed_colors();
+			if let Some(background) = background {
+				svg.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n", number(x), number(top), number(width), number(line_height), background.hex()));
+			}
+			if run.text.trim().is_empty() && !run.style.underline && !run.style.strikethrough {
+				continue;
+			}
+			// The text length stretches the glyphs onto the grid, in case the font is not exactly 0.6 times as wide as high.
+			let mut attributes = format!("x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"", number(x), number(top + line_height * 0.8), number(width));
+			if let Some(foreground) = foreground {
+				attributes.push_str(&format!(" fill=\"{}\"", foreground.hex()));
+			}
+			if run.style.bold {
+				attributes.push_str(" font-weight=\"bold\"");
+			}
+			if run.style.dim {
+				attributes.push_str(" opacity=\"0.5\"");
+			}
+			if run.style.italic {
+				attributes.push_str(" font-style=\"italic\"");
+			}
+			match (run.style.underline, run.style.strikethrough) {
+				(true, true) => attributes.push_str(" text-decoration=\"underline line-through\""),
+				(true, false) => attributes.push_str(" text-decoration=\"underline\""),
+				(false, true) => attributes.push_str(" text-decoration=\"line-through\""),
+				(false, false) => {}
+			}
+			svg.push_str(&format!("<text {attributes}>{}</text>\n", escape_html(&run.text)));
+		}
+	}
+	svg.push_str("</g>\n</svg>\n");
+	svg
+}
+
+/// Formats the number with at most two decimals, without trailing zeros.
+fn number(value: f32) -> String {
+	let formatted = format!("{value:.2}");
+	formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,202 @@
+use crate::color::{ColorDepth, Rgb};
+use crate::width::char_width;
+use std::borrow::Cow;
+
+const ESCAPE: char = '\u{1B}';
+const BELL: char = '\u{07}';
+const CSI: char = '\u{9B}';
+const OSC: char = '\u{9D}';
+const STRING_TERMINATOR: char = '\u{9C}';
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Removes all ANSI escape sequences from the text, leaving the plain text.
+/// Text without escape sequences is returned as is, without allocation.
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+	if !text.contains([ESCAPE, CSI, OSC]) {
+		return Cow::Borrowed(text);
+	}
+	
+	let mut output = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find([ESCAPE, CSI, OSC]) {
+		output.push_str(&rest[..start]);
+		rest = &rest[start..];
+		rest = &rest[escape_sequence_length(rest)..];
+	}
+	output.push_str(rest);
+	Cow::Owned(output)
+}
+
+/// Reduces the escape sequences to the features of the terminal: Colors are mapped to the nearest color of the depth,
+/// italics (SGR 3 and 23) and hyperlinks (OSC 8, the text of links is kept) are removed when not supported.
+/// Text without escape sequences is returned as is, without allocation.
+pub fn reduce_ansi<'a>(text: &'a str, depth: ColorDepth, italic: bool, hyperlinks: bool) -> Cow<'a, str> {
+	if (depth == ColorDepth::TrueColor && italic && hyperlinks) || !text.contains([ESCAPE, CSI, OSC]) {
+		return Cow::Borrowed(text);
+	}
+	
+	let mut output = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find([ESCAPE, CSI, OSC]) {
+		output.push_str(&rest[..start]);
+		rest = &rest[start..];
+		let length = escape_sequence_length(rest);
+		let sequence = &rest[..length];
+		rest = &rest[length..];
+		
+		match sgr_parameters(sequence) {
+			Some((introducer, parameters)) => {
+				let reduced = reduce_sgr(parameters, depth, italic);
+				// Sequences which only contained italic parameters are dropped, an empty sequence would be a reset.
+				if !reduced.is_empty() || parameters.is_empty() {
+					output.push_str(&format!("{introducer}{reduced}m"));
+				}
+			}
+			None if !hyperlinks && is_hyperlink(sequence) => {}
+			None => output.push_str(sequence),
+		}
+	}
+	output.push_str(rest);
+	Cow::Owned(output)
+}
+
+/// The introducer and parameters of an SGR sequence (like 'ESC[1;31m').
+fn sgr_parameters(sequence: &str) -> Option<(&str, &str)> {
+	let parameters = sequence.strip_prefix("\u{1B}[").or_else(|| sequence.strip_prefix(CSI))?.strip_suffix('m')?;
+	Some((&sequence[..sequence.len() - parameters.len() - 1], parameters))
+}
+
+/// Maps the colors of an SGR sequence to the depth and removes the italic parameters, if not supported.
+fn reduce_sgr(parameters: &str, depth: ColorDepth, italic: bool) -> String {
+	let parameters = parameters.split(';').collect::<Vec<_>>();
+	let mut reduced = Vec::new();
+	let mut index = 0;
+	while index < parameters.len() {
+		let parameter = parameters[index];
+		index += 1;
+		match parameter.parse::<u8>() {
+			Ok(3 | 23) if !italic => {}
+			Ok(code @ (38 | 48)) => {
+				// Extended colors: '5;index' of the 256 color palette or '2;r;g;b'. Their components could look like other parameters.
+				let length = match parameters.get(index) {
+					Some(&"5") => 2,
+					Some(&"2") => 4,
+					_ => 1,
+				}.min(parameters.len() - index);
+				let components = &parameters[index..index + length];
+				index += length;
+				let numbers = components.iter().map(|component| component.parse::<u8>().ok()).collect::<Option<Vec<_>>>();
+				let color = match numbers.as_deref() {
+					Some(&[5, color]) if matches!(depth, ColorDepth::Colors16 | ColorDepth::Colors8) => Some(Rgb::from_index(color)),
+					Some(&[2, r, g, b]) if depth != ColorDepth::TrueColor => Some(Rgb::new(r, g, b)),
+					_ => None,
+				};
+				match color {
+					Some(color) if code == 38 => reduced.push(color.foreground(depth)),
+					Some(color) => reduced.push(color.background(depth)),
+					None => reduced.extend([parameter].iter().chain(components).map(|component| component.to_string())),
+				}
+			}
+			// Bright colors, which 8 color terminals do not have:
+			Ok(code @ 90..=97) if depth == ColorDepth::Colors8 => reduced.push(Rgb::from_index(code - 90 + 8).foreground(depth)),
+			Ok(code @ 100..=107) if depth == ColorDepth::Colors8 => reduced.push(Rgb::from_index(code - 100 + 8).background(depth)),
+			_ => reduced.push(parameter.to_string()),
+		}
+	}
+	reduced.join(";")
+}
+
+/// Whether the sequence is an OSC 8 hyperlink (starting or ending a link).
+fn is_hyperlink(sequence: &str) -> bool {
+	sequence.strip_prefix("\u{1B}]").or_else(|| sequence.strip_prefix(OSC)).is_some_and(|command| command.starts_with("8;"))
+}
+
+/// Amount of terminal columns the text takes, when printed.
+/// ANSI escape sequences take no space, wide characters (like CJK or emoji) take two columns and combining characters none.
+/// Line breaks are not handled, the width of all lines is summed up.
+pub fn visible_width(text: &str) -> usize {
+	let mut width = 0;
+	let mut previous = None;
+	let mut open_flag = false;
+	for char in strip_ansi(text).chars() {
+		let is_regional_indicator = ('\u{1F1E6}'..='\u{1F1FF}').contains(&char);
+		width += match previous {
+			// Emoji joined with a zero width joiner are displayed as a single emoji.
+			Some(ZERO_WIDTH_JOINER) => 0,
+			// Flags consist of two regional indicators, the pair is displayed as one wide symbol.
+			_ if is_regional_indicator && open_flag => 0,
+			_ => char_width(char),
+		};
+		open_flag = is_regional_indicator && !open_flag;
+		previous = Some(char);
+	}
+	width
+}
+
+/// Wraps the text in OSC 8 escape sequences, which make it a clickable link to the URL in supporting terminals.
+/// Other terminals just print the text. Whitespaces and control characters in the URL are percent-encoded,
+/// so the URL cannot end the escape sequence.
+pub fn hyperlink(url: &str, text: &str) -> String {
+	let mut encoded = String::with_capacity(url.len());
+	for char in url.chars() {
+		if char.is_whitespace() || char.is_control() {
+			for byte in char.encode_utf8(&mut [0; 4]).bytes() {
+				encoded.push_str(&format!("%{byte:02X}"));
+			}
+		} else {
+			encoded.push(char);
+		}
+	}
+	format!("{}{text}{}", hyperlink_sequence(&encoded), hyperlink_sequence(""))
+}
+
+/// OSC 8 escape sequence, which starts a link to the URL or ends the link if the URL is empty.
+/// Terminated by BEL instead of 'ESC \', as backslashes would have to be escaped in string literals.
+pub(crate) fn hyperlink_sequence(url: &str) -> String {
+	format!("\u{1B}]8;;{url}{BELL}")
+}
+
+/// Length in bytes of the escape sequence at the start of the text. Unterminated sequences span the rest of the text.
+/// Handles CSI sequences (like colors), string sequences like OSC (like hyperlinks) and two character escape sequences.
+pub(crate) fn escape_sequence_length(text: &str) -> usize {
+	let mut iterator = text.char_indices();
+	let Some((_, opener)) = iterator.next() else {
+		return 0;
+	};
+	let introducer = match opener {
+		CSI => '[',
+		OSC => ']',
+		ESCAPE => match iterator.next() {
+			Some((_, char)) => char,
+			None => return text.len(),
+		},
+		_ => return 0,
+	};
+	
+	match introducer {
+		// Control Sequence Introducer: Parameter and intermediate bytes, ended by a final byte (like 'm').
+		'[' => iterator.find(|(_, char)| ('\u{40}'..='\u{7E}').contains(char))
+			.map_or(text.len(), |(index, char)| index + char.len_utf8()),
+		// Operating System Command and other string sequences: Ended by the string terminator 'ESC \' (or BEL for OSC).
+		// Any other escape sequence also ends the string, it is then no longer part of this sequence.
+		']' | 'P' | 'X' | '^' | '_' => iterator.find(|(_, char)| matches!(*char, BELL | STRING_TERMINATOR | ESCAPE))
+			.map_or(text.len(), |(index, char)| match char {
+				ESCAPE if text[index..].starts_with("\u{1B}\\") => index + 2,
+				ESCAPE => index,
+				_ => index + char.len_utf8(),
+			}),
+		// Two character sequences (like 'ESC 7'), possibly with intermediate bytes (like 'ESC ( B').
+		_ => {
+			let mut length = ESCAPE.len_utf8() + introducer.len_utf8();
+			let mut char = introducer;
+			while ('\u{20}'..='\u{2F}').contains(&char) {
+				let Some((index, next)) = iterator.next() else {
+					return text.len();
+				};
+				length = index + next.len_utf8();
+				char = next;
+			}
+			length
+		}
+	}
+}
@@ -1,79 +1,55 @@
-use crate::palette::Palette;
-use proc_macro::TokenStream;
-use std::iter::Peekable;
-use std::str::FromStr;
+use crate::helpers::{collect_first_argument, map_string_literals, CompileError};
+use ecc_ansi_lib_core::Palette;
+use proc_macro::{Span, TokenStream, TokenTree};
 
 pub fn ansi_impl(input: TokenStream) -> TokenStream {
+	ansi(input).unwrap_or_else(CompileError::into_token_stream)
+}
+
+fn ansi(input: TokenStream) -> Result<TokenStream, CompileError> {
 	let mut iterator = input.into_iter();
 	
 	// The first argument is the "string literal", or something that contains/yields string literals.
-	let format_string = crate::helpers::collect_first_argument(&mut iterator);
+	let format = collect_first_argument(&mut iterator)?;
 	// Now collect all remaining tokens (the color palette) as string and parse them into a Palette.
-	let palette = Palette::from_string_tokens(iterator.map(|token_tree| token_tree.to_string()).collect::<Vec<_>>());
+	let palette = parse_palette(&palette_tokens(iterator)?, Span::call_site())?;
 	
 	// Replace all color-symbols in the format string.
-	let output = apply_ansi(&format_string, &palette);
-	
-	// Finally return the format as TokenStream again.
-	// This conversion to string is performed, as copying/modifying the original token stream is kind of huge and error-prone effort.
-	TokenStream::from_str(&output).unwrap()
+	apply_ansi(format, &palette)
 }
 
-fn apply_ansi(format: &str, palette: &Palette) -> String {
-	let mut output = String::with_capacity(format.len());
-	let mut iterator = format.chars().into_iter().peekable();
-	
-	while let Some(char) = iterator.next() {
-		// Iterate over each character in the format:
-		output.push(char); // Add each to the output (including opening '"' symbols).
-		if char == '"' {
-			// When a string literal opening was found, process it.
-			process_string_literal(&mut iterator, &palette, &mut output)
-		}
-	}
-	
-	output
+/// Parses the palette tokens, errors point at the offending palette token if known, else at the fallback span.
+pub(crate) fn parse_palette(tokens: &[(String, Span)], span: Span) -> Result<Palette, CompileError> {
+	Palette::from_string_tokens(tokens.iter().map(|(token, _)| token.clone()).collect::<Vec<_>>())
+		.map_err(|error| {
+			let span = error.token().and_then(|index| tokens.get(index)).map_or(span, |(_, span)| *span);
+			CompileError::new(format!("Could not parse color palette: {error}"), span)
+		})
 }
 
-fn process_string_literal<T: Iterator<Item = char>>(iterator: &mut Peekable<T>, palette: &Palette, output: &mut String) {
-	loop {
-		let string_literal_char = iterator.next().unwrap_or_else(|| panic!("Unterminated string literal."));
-		match string_literal_char {
-			'\\' => {
-				// Encountered an escaping symbol. Disregard whatever the next symbol would be. In a well-formed code, this should work just fine.
-				output.push('\\');
-				output.push(iterator.next().unwrap_or_else(|| panic!("Unterminated string literal.")));
-			}
-			'"' => {
-				// Encountered string literal closer.
-				output.push('"');
-				break;
+/// Marks the text of a palette file in the palette tokens, as raw string literal like '__palette_text r#"@default warning 255 200 0"#'.
+/// Palette files are split by the tokenizer of `Palette::parse()` (like in the command-line tool), not as Rust tokens.
+pub(crate) const PALETTE_TEXT: &str = "__palette_text";
+
+/// Converts the palette tokens to strings, keeping their spans for error reporting.
+pub(crate) fn palette_tokens(mut iterator: impl Iterator<Item = TokenTree>) -> Result<Vec<(String, Span)>, CompileError> {
+	let mut tokens = Vec::new();
+	while let Some(token) = iterator.next() {
+		match token {
+			TokenTree::Ident(ident) if ident.to_string() == PALETTE_TEXT => {
+				let (literal, span) = iterator.next().map_or((String::new(), ident.span()), |token| (token.to_string(), token.span()));
+				let text = literal.strip_prefix('r').map(|literal| literal.trim_matches('#')).and_then(|literal| literal.strip_prefix('"')?.strip_suffix('"'))
+					.ok_or_else(|| CompileError::new(format!("Expected the palette text as raw string literal after '{PALETTE_TEXT}'"), span))?;
+				tokens.extend(Palette::tokenize(text).into_iter().map(|token| (token, span)));
 			}
-			'«' => process_color_format(iterator, palette, output),
-			// In all other cases just keep the symbol as-is.
-			_ => output.push(string_literal_char),
+			token => tokens.push((token.to_string(), token.span())),
 		}
 	}
+	Ok(tokens)
 }
 
-fn process_color_format<T: Iterator<Item = char>>(iterator: &mut Peekable<T>, palette: &Palette, output: &mut String) {
-	// Check if this is an escaped argument (starting with '««'), if so just ignore it and continue with the string literal.
-	let next = *iterator.peek().unwrap_or_else(|| panic!("Unterminated string literal ('«»'). Output: '{output}'"));
-	if next == '«' {
-		iterator.next().unwrap(); // Consume the peeked symbol.
-		output.push('«'); // At the full opener (as addition below is not executed).
-		return;
-	}
-	
-	let mut color_format_buffer = String::new();
-	// Now collect all color format code characters (until it stops).
-	loop {
-		let string_literal_argument_char = iterator.next().unwrap_or_else(|| panic!("Unterminated color format code ('«»'). Output: '{output}'"));
-		if string_literal_argument_char == '»' {
-			break;
-		}
-		color_format_buffer.push(string_literal_argument_char);
-	}
-	// Resolve and append the ANSI color.
-	output.push_str(&palette.lookup(&color_format_buffer));
+/// Replaces all «color» codes in the string literals of the format.
+/// Tokens which are not string literals stay untouched, thus macros like concat!() can be used.
+fn apply_ansi(format: TokenStream, palette: &Palette) -> Result<TokenStream, CompileError> {
+	map_string_literals(format, &mut |content| ecc_ansi_lib_core::render(content, palette).map_err(|error| error.to_string()))
 }
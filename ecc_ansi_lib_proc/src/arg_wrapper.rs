@@ -1,11 +1,16 @@
-use proc_macro::{TokenStream, TokenTree};
+use proc_macro::{Delimiter, TokenStream, TokenTree};
 use std::iter::Peekable;
-use std::str::FromStr;
-use crate::helpers::{collect_first_argument, expect_string_literal, matches_string};
+use crate::helpers::{collect_first_argument, expect_string_literal, map_string_literals, matches_string, CompileError};
 
 // This macro eats two formats:
 // - arg_wrapper_impl!(<first argument, should contain string literals>, <string literal for argument highlight color>)
 // - arg_wrapper_impl!(<first argument, should contain string literals>, <string literal for argument highlight color>, <string literal for normal text color>)
+// Instead of a single highlight color, a list of colors can be provided ('["y", "c"]'), one per argument in order of the placeholders.
+// Arguments after the last color of the list use the last color.
+// Arguments may also contain their own highlight color as inline code ('{name:«y»}'), which is removed from the argument.
+// Optionally after the colors, named colors for the kind of argument can be provided: 'debug = "m"' and 'number = "c"'.
+// They are used instead of the highlight color for Debug ('{:?}', '{:#x?}') and numeric ('{:x}', '{:X}', '{:o}', '{:b}', '{:e}', '{:E}') arguments.
+// Inline colors are still preferred over named colors.
 // Each string literal passed as first argument to this maro will have its arguments wrapped with the color codes provided to this macro.
 // - First color argument is the color used to highlight arguments.
 // - Second color argument will be used for non-argument text. If omitted the 'reset' color is used (whatever the terminal users as default).
@@ -13,107 +18,200 @@ use crate::helpers::{collect_first_argument, expect_string_literal, matches_stri
 // Macro will prevent redundant color-codes within a string literal ('«»«»').
 // Result must be sent through ansi!() to actually apply the color codes. This is done externally to allow the usage of custom color palettes.
 pub fn arg_wrapper_impl(input: TokenStream) -> TokenStream {
+	arg_wrapper(input).unwrap_or_else(CompileError::into_token_stream)
+}
+
+fn arg_wrapper(input: TokenStream) -> Result<TokenStream, CompileError> {
 	let mut iterator = input.into_iter();
 	
 	// The first argument is the "string literal", or something that contains/yields string literals.
-	let format = collect_first_argument(&mut iterator);
+	let format = collect_first_argument(&mut iterator)?;
 	// The second arguments is the highlight color as string literal.
-	let highlight_color = match expect_string_literal(&mut iterator) {
-		Ok(value) => value,
-		Err(message) => panic!("Could not parse second (highlight color) argument: {message}"),
-	};
-	// The OPTIONAL third argument is the normal text color as string literal.
-	let normal_color = if let Some(token) = iterator.next() {
-		if let TokenTree::Punct(punctuation) = token {
-			if punctuation.as_char() != ',' {
-				panic!("If a third argument is supplied the separator after the second argument should be a comma.");
-			}
-		} else {
-			panic!("Only allowing another string literal after the second argument, separated by a comma.");
+	let highlight_colors = expect_highlight_colors(&mut iterator).map_err(|error| error.context("Could not parse second (highlight color) argument"))?;
+	// The OPTIONAL third argument is the normal text color as string literal, followed by OPTIONAL named colors.
+	let mut normal_color = None;
+	let (mut debug_color, mut number_color) = (None, None);
+	let mut iterator = iterator.peekable();
+	while let Some(token) = iterator.next() {
+		match &token {
+			TokenTree::Punct(punctuation) if punctuation.as_char() == ',' => {},
+			TokenTree::Punct(_) => return Err(CompileError::new("If more arguments are supplied the separator after the second argument should be a comma.", token.span())),
+			_ => return Err(CompileError::new(format!("Unexpected token '{token}' after the last color argument, expected a comma."), token.span())),
 		}
-		
-		match expect_string_literal(&mut iterator) {
-			Ok(value) => value,
-			Err(message) => panic!("Could not parse second (highlight color) argument: {message}"),
+		let Some(TokenTree::Ident(name)) = iterator.peek().cloned() else {
+			if iterator.peek().is_none() {
+				// Trailing comma.
+				break;
+			}
+			if normal_color.is_some() || debug_color.is_some() || number_color.is_some() {
+				return Err(CompileError::new("Only allowing named colors (like 'debug = \"m\"') after the normal color argument.", iterator.peek().unwrap().span()));
+			}
+			normal_color = Some(expect_string_literal(&mut iterator).map_err(|error| error.context("Could not parse third (normal color) argument"))?);
+			continue;
+		};
+		iterator.next();
+		let target = match name.to_string().as_str() {
+			"debug" => &mut debug_color,
+			"number" => &mut number_color,
+			other => return Err(CompileError::new(format!("Unknown named color '{other}', expected 'debug' or 'number'."), name.span())),
+		};
+		match iterator.next() {
+			Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+			_ => return Err(CompileError::new(format!("Expected '=' and a color after '{name}'."), name.span())),
 		}
-	} else {
-		"".to_string()
-	};
+		*target = Some(expect_string_literal(&mut iterator).map_err(|error| error.context(&format!("Could not parse the '{name}' color")))?);
+	}
 	
 	// Actually wrap all arguments with «» color codes.
-	let output = wrap_arguments_with_color_codes(
-		format,
-		format!("«{}»", normal_color),
-		format!("«{}»", highlight_color),
-	);
+	let normal = format!("«{}»", normal_color.unwrap_or_default());
+	let mut highlights = Highlights::new(&highlight_colors, debug_color.as_deref(), number_color.as_deref());
+	// String literals may end inside an argument (like in concat!("{", "}")), then the next literal continues it.
+	let mut open_argument = false;
+	map_string_literals(format, &mut |content| wrap_arguments_with_color_codes(content, &normal, &mut highlights, &mut open_argument))
+}
+
+/// The second argument is either a single color, or a list of colors in brackets.
+pub(crate) fn expect_highlight_colors<T: Iterator<Item = TokenTree>>(iterator: &mut T) -> Result<Vec<String>, CompileError> {
+	let mut iterator = iterator.peekable();
+	let Some(TokenTree::Group(group)) = iterator.peek().filter(|token| matches!(token, TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket)).cloned() else {
+		return Ok(vec![expect_string_literal(&mut iterator)?]);
+	};
+	iterator.next();
 	
-	// Finally return the format as TokenStream again.
-	// This conversion to string is performed, as copying/modifying the original token stream is kind of huge and error-prone effort.
-	TokenStream::from_str(&output).unwrap()
+	let mut colors = Vec::new();
+	let mut list = group.stream().into_iter().peekable();
+	while list.peek().is_some() {
+		colors.push(expect_string_literal(&mut list)?);
+		match list.next() {
+			None => break,
+			Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+			Some(token) => return Err(CompileError::new(format!("Expected a comma between the highlight colors, got '{token}'"), token.span())),
+		}
+	}
+	if colors.is_empty() {
+		return Err(CompileError::new("Expected at least one highlight color in the list", group.span()));
+	}
+	Ok(colors)
+}
+
+/// Highlight colors of the arguments, in order of the placeholders (also across string literals).
+pub(crate) struct Highlights {
+	colors: Vec<String>,
+	/// Colors for Debug and numeric arguments, if provided.
+	debug: Option<String>,
+	number: Option<String>,
+	next: usize,
+	/// The highlight color of the argument, which just finished in the current string literal.
+	current: Option<String>,
 }
 
-fn wrap_arguments_with_color_codes(format: String, normal: String, highlight: String) -> String {
-	if format.is_empty() {
-		return format;
+impl Highlights {
+	pub(crate) fn new(colors: &[String], debug: Option<&str>, number: Option<&str>) -> Highlights {
+		Highlights {
+			colors: colors.iter().map(|color| format!("«{color}»")).collect(),
+			debug: debug.map(|color| format!("«{color}»")),
+			number: number.map(|color| format!("«{color}»")),
+			next: 0,
+			current: None,
+		}
 	}
 	
-	let mut output = String::with_capacity(format.len());
-	let mut iterator = format.chars().peekable();
-	
-	while let Some(char) = iterator.next() {
-		// Iterate over each character in the format:
-		output.push(char); // Add each to the output (including opening '"' symbols).
-		if char == '"' {
-			// When a string literal opening was found, process it.
-			process_string_literal(&mut iterator, &mut output, &normal, &highlight)
+	/// Color of the next argument, arguments after the last color of the list use the last color.
+	/// The argument content (like 'name:>8?') decides whether the Debug or number color is used instead.
+	fn next(&mut self, content: &str, inline: Option<String>) -> String {
+		let color = self.colors[self.next.min(self.colors.len() - 1)].clone();
+		self.next += 1;
+		if let Some(inline) = inline {
+			return format!("«{inline}»");
 		}
+		let kind_color = match format_type(content) {
+			Some('?') => self.debug.clone(),
+			Some('x' | 'X' | 'o' | 'b' | 'e' | 'E') => self.number.clone(),
+			_ => None,
+		};
+		kind_color.unwrap_or(color)
 	}
-	
-	output
 }
 
-fn process_string_literal<T: Iterator<Item = char> + Clone>(iterator: &mut Peekable<T>, output: &mut String, normal: &str, highlight: &str) {
-	// If string literal does not start with an argument, add the normal text color color-code.
-	let first_string_literal_char = *iterator.peek().unwrap_or_else(|| panic!("Unterminated string literal. Only encountered opener so far."));
-	// If {, but not {{, then skip adding the normal color.
-	if !(first_string_literal_char == '{' && !matches_string(iterator, "{{")) {
-		output.push_str(&normal);
+/// The type of the format specification ('name:>8x' => 'x'), which is always its last character. None for Display.
+/// The fill character can never be last, as it is always followed by the alignment.
+fn format_type(content: &str) -> Option<char> {
+	let (_, specification) = content.split_once(':')?;
+	specification.chars().last().filter(|char| char.is_ascii_alphabetic() || *char == '?')
+}
+
+/// Wraps the arguments of a single format string, like the string literals of 'arg_wrapper_impl!()'.
+pub(crate) fn wrap_arguments(content: &str, normal_color: &str, highlights: &mut Highlights) -> Result<String, String> {
+	let mut open_argument = false;
+	let output = wrap_arguments_with_color_codes(content, &format!("«{normal_color}»"), highlights, &mut open_argument)?;
+	if open_argument {
+		return Err(format!("Unterminated argument in format '{content}', expected a closing '}}'."));
+	}
+	Ok(output)
+}
+
+fn wrap_arguments_with_color_codes(content: &str, normal: &str, highlights: &mut Highlights, open_argument: &mut bool) -> Result<String, String> {
+	let mut output = String::with_capacity(content.len());
+	process_string_literal(&mut content.chars().peekable(), &mut output, normal, highlights, open_argument)?;
+	Ok(output)
+}
+
+fn process_string_literal<T: Iterator<Item = char> + Clone>(iterator: &mut Peekable<T>, output: &mut String, normal: &str, highlights: &mut Highlights, open_argument: &mut bool) -> Result<(), String> {
+	let mut previously_finished_argument = false;
+	if !*open_argument {
+		// Consecutive arguments are only merged within the same string literal:
+		highlights.current = None;
+	}
+	if *open_argument {
+		// The previous string literal ended inside an argument, finish it first.
+		process_string_literal_argument_content(iterator, output, normal, open_argument);
+		if *open_argument {
+			return Ok(());
+		}
+		previously_finished_argument = true;
+	} else if iterator.peek() != Some(&'{') || matches_string(iterator, "{{") {
+		// If string literal does not start with an argument, add the normal text color color-code.
+		// If {, but not {{, then skip adding the normal color.
+		output.push_str(normal);
 	}
 	
 	// Process every char, until the string literal closes.
-	let mut previously_finished_argument = false;
 	loop {
 		let mut just_finished_argument = false;
-		let string_literal_char = iterator.next().unwrap_or_else(|| panic!("Unterminated string literal."));
+		let Some(string_literal_char) = iterator.next() else {
+			// Encountered string literal end.
+			if previously_finished_argument || normal != "«»" {
+				// Always append a color-reset. (If string literals are merged, this can be redundant).
+				// Except: There is no need to reset though, when the normal color is reset anyway.
+				//         But if there just was an argument, we got to reset again. As the argument
+				//          termination won't reset when it detects literal termination.
+				output.push_str("«»");
+			}
+			return Ok(());
+		};
 		match string_literal_char {
 			'\\' => {
 				// Encountered an escaping symbol. Disregard whatever the next symbol would be. In a well-formed code, this should work just fine.
 				output.push('\\');
-				output.push(iterator.next().unwrap_or_else(|| panic!("Unterminated string literal.")));
-			}
-			'"' => {
-				// Encountered string literal closer.
-				if previously_finished_argument || normal != "«»" {
-					// Always append a color-reset. (If string literals are merged, this can be redundant).
-					// Except: There is no need to reset though, when the normal color is reset anyway.
-					//         But if there just was an argument, we got to reset again. As the argument
-					//          termination won't reset when it detects literal termination.
-					output.push_str("«»");
-				}
-				output.push('"');
-				break;
+				output.push(iterator.next().ok_or("Unterminated string literal escape sequence.")?);
 			}
 			// If an argument-starter is encountered, handle that.
 			'{' => {
 				// Check if this is an escaped argument (starting with '{{'), if so just ignore it and continue with the string literal.
-				let peeked = *iterator.peek().unwrap_or_else(|| panic!("Unterminated string literal argument ('{{}}'). Output: '{output}'"));
-				if peeked == '{' {
+				if iterator.peek() == Some(&'{') {
 					// Is escaped!
 					iterator.next().unwrap(); // Consume the peeked symbol.
 					output.push_str("{{"); // At the full opener.
 				} else {
 					// Not escaped - actual argument.
-					process_string_literal_argument(iterator, output, normal, highlight, previously_finished_argument);
+					if !previously_finished_argument {
+						highlights.current = None;
+					}
+					process_string_literal_argument(iterator, output, normal, highlights, open_argument)?;
+					if *open_argument {
+						// The string literal ended inside of the argument, the next string literal will continue it.
+						return Ok(());
+					}
 					just_finished_argument = true;
 				}
 			},
@@ -124,29 +222,64 @@ fn process_string_literal<T: Iterator<Item = char> + Clone>(iterator: &mut Peeka
 	}
 }
 
-fn process_string_literal_argument<T: Iterator<Item = char> + Clone>(iterator: &mut Peekable<T>, output: &mut String, normal: &str, highlight: &str, just_finished_an_argument: bool) {
+fn process_string_literal_argument<T: Iterator<Item = char> + Clone>(iterator: &mut Peekable<T>, output: &mut String, normal: &str, highlights: &mut Highlights, open_argument: &mut bool) -> Result<(), String> {
+	// Look at the whole argument first, it may contain an inline highlight color.
+	let content = iterator.clone().take_while(|char| *char != '}').collect::<String>();
+	let (content_without_color, inline_color) = extract_inline_color(&content)?;
+	let highlight = highlights.next(&content_without_color, inline_color);
+	
 	// 100% inside an argument now. Prefix it with the highlight color.
-	if !just_finished_an_argument {
+	if highlights.current.as_ref() != Some(&highlight) {
 		// Do not put a highlight color, if we still are using the highlight color (cause an argument finished right before this one).
 		output.push_str(&highlight);
 	}
+	highlights.current = Some(highlight);
 	output.push('{');
-	
+	output.push_str(&content_without_color);
+	// Consume the content, which was only peeked.
+	for _ in content.chars() {
+		iterator.next();
+	}
+	process_string_literal_argument_content(iterator, output, normal, open_argument);
+	Ok(())
+}
+
+/// Removes the inline highlight color from the argument content ('name:«y»' => 'name'), so that it stays a valid format argument.
+fn extract_inline_color(content: &str) -> Result<(String, Option<String>), String> {
+	let Some(start) = content.find('«') else {
+		return Ok((content.to_string(), None));
+	};
+	let end = content[start..].find('»').map(|index| start + index).ok_or_else(|| format!("Unterminated inline highlight color in argument '{{{content}}}', expected a closing '»'."))?;
+	let color = content[(start + '«'.len_utf8())..end].to_string();
+	let mut content = format!("{}{}", &content[..start], &content[(end + '»'.len_utf8())..]);
+	if content.ends_with(':') {
+		// The format specification is empty without the color, remove the separator too.
+		content.pop();
+	}
+	Ok((content, Some(color)))
+}
+
+fn process_string_literal_argument_content<T: Iterator<Item = char> + Clone>(iterator: &mut Peekable<T>, output: &mut String, normal: &str, open_argument: &mut bool) {
 	// Now loop over all characters in the argument (until it stops).
 	loop {
-		let string_literal_argument_char = iterator.next().unwrap_or_else(|| panic!("Unterminated string literal argument ('{{}}'). Output: '{output}'"));
+		let Some(string_literal_argument_char) = iterator.next() else {
+			// The string literal ended, before the argument was closed.
+			*open_argument = true;
+			return;
+		};
 		output.push(string_literal_argument_char); // Add any char to the output, nothing will be color-prefixed here.
 		// Encountered a (potential) closing char, handle it.
 		if string_literal_argument_char == '}' {
+			*open_argument = false;
 			// The next char is important to know if this is escaped.
-			let next_char = *iterator.peek().unwrap_or_else(|| panic!("Unterminated string literal after argument."));
-			if !(next_char == '"' || (next_char == '{' && !matches_string(iterator, "{{"))) {
+			let next_char = iterator.peek();
+			if !(next_char.is_none() || (next_char == Some(&'{') && !matches_string(iterator, "{{"))) {
 				// Always reset the color after an argument.
-				// Except: The next character is '"' (then «» is added by the literal string termination).
+				// Except: The string literal ends (then «» is added by the literal string termination).
 				// Except: There is another argument following. Meaning '{' but not '{{' follows.
-				output.push_str(&normal);
+				output.push_str(normal);
 			}
-			break;
+			return;
 		}
 	}
 }
@@ -0,0 +1,112 @@
+use crate::definition::{find_attribute, parse_crate_path, parse_key_values, parse_palette_value, Body, Definition, Fields, DEFAULT_CRATE_PATH};
+use crate::helpers::{expect_string_literal, CompileError};
+use ecc_ansi_lib_core::Palette;
+use proc_macro::{Group, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::str::FromStr;
+
+// Derives 'Debug' for structs and enums, which prints like '#[derive(Debug)]' but colored.
+// The container attribute '#[ansi_debug(...)]' can change the colors and palette:
+// - 'field', 'variant', 'string', 'number' and 'punctuation' set the color of that part of the output.
+// - 'palette = { ... }' replaces the palette, use '@default' to extend the default palette.
+// - 'crate = path' replaces the path of this crate in the generated code (default '::ecc_ansi_lib').
+// The colors are rendered here for the dark and light theme. Which one is used (or none) is decided at runtime.
+pub fn ansi_debug_impl(input: TokenStream) -> TokenStream {
+	ansi_debug(input).unwrap_or_else(|error| {
+		// Derives generate items, where compile_error!() must be followed by a semicolon.
+		let mut tokens = error.into_token_stream();
+		tokens.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+		tokens
+	})
+}
+
+/// Color keys of the attribute, with their default colors.
+const STYLE_KEYS: [(&str, &str); 5] = [("field", "c"), ("variant", "y"), ("string", "g"), ("number", "lb"), ("punctuation", "gr")];
+
+fn ansi_debug(input: TokenStream) -> Result<TokenStream, CompileError> {
+	let definition = Definition::parse(input, "AnsiDebug", Some("::std::fmt::Debug"))?;
+	let (styles, crate_path) = parse_styles(find_attribute(&definition.attributes, "ansi_debug")?)?;
+	
+	let body = match &definition.body {
+		Body::Struct(fields) => {
+			let accessors = match fields {
+				Fields::Named(names) => names.iter().map(|name| format!("&self.{name}")).collect(),
+				Fields::Unnamed(count) => (0..*count).map(|index| format!("&self.{index}")).collect(),
+				Fields::Unit => Vec::new(),
+			};
+			debug_builder(&definition.name, fields, &accessors)
+		}
+		Body::Enum(variants) if variants.is_empty() => "match *self {}".to_string(),
+		Body::Enum(variants) => {
+			let arms = variants.iter().map(|variant| {
+				let (pattern, bindings) = variant.fields.pattern();
+				let builder = debug_builder(variant.name.trim_start_matches("r#"), &variant.fields, &bindings);
+				format!("Self::{} {pattern} => {builder},\n", variant.name)
+			}).collect::<String>();
+			format!("match self {{ {arms} }}")
+		}
+	};
+	
+	let code = definition.implement("::std::fmt::Debug", &format!("
+		const STYLES: [{crate_path}::DebugStyle; 2] = [{}, {}];
+		{crate_path}::debug_colored(formatter, &STYLES, &{crate_path}::DebugFn(|formatter: &mut ::std::fmt::Formatter<'_>| {{
+			{body}
+		}}))
+	", styles[0], styles[1]));
+	TokenStream::from_str(&code).map_err(|error| CompileError::new(format!("Could not generate Debug implementation: {error:?}"), Span::call_site()))
+}
+
+/// Parses the arguments of '#[ansi_debug(...)]' and renders the colors as 'DebugStyle' for the dark and light theme.
+/// Also returns the path of this crate.
+fn parse_styles(arguments: Option<Group>) -> Result<([String; 2], String), CompileError> {
+	let mut colors = STYLE_KEYS.map(|(key, color)| (key, color.to_string()));
+	let mut palette = None;
+	let mut crate_path = DEFAULT_CRATE_PATH.to_string();
+	let pairs = match &arguments {
+		Some(arguments) => parse_key_values(arguments)?,
+		None => Vec::new(),
+	};
+	for (key, value) in pairs {
+		let key_name = key.to_string();
+		if key_name == "palette" {
+			palette = Some((key, value));
+		} else if key_name == "crate" {
+			crate_path = parse_crate_path(&key, value)?;
+		} else if let Some((_, color)) = colors.iter_mut().find(|(name, _)| *name == key_name) {
+			*color = expect_string_literal(&mut value.into_iter()).map_err(|error| error.context(&format!("Could not parse the '{key_name}' color")))?;
+		} else {
+			let keys = STYLE_KEYS.map(|(name, _)| name).join(", ");
+			return Err(CompileError::new(format!("Unknown key '{key_name}', expected 'palette', 'crate' or one of: {keys}"), key.span()));
+		}
+	}
+	let palette = parse_palette_value(palette)?;
+	
+	let span = arguments.map_or_else(Span::call_site, |arguments| arguments.span());
+	let render = |palette: &Palette| -> Result<String, CompileError> {
+		let mut style = format!("{crate_path}::DebugStyle {{");
+		for (key, color) in &colors {
+			let sequence = ecc_ansi_lib_core::render(&format!("«{color}»"), palette)
+				.map_err(|error| CompileError::new(format!("Could not render the '{key}' color: {error}"), span))?;
+			style.push_str(&format!(" {key}: {sequence:?},"));
+		}
+		style.push_str(" }");
+		Ok(style)
+	};
+	Ok(([render(&palette)?, render(&palette.with_theme("light"))?], crate_path))
+}
+
+/// Code using the Debug builders of the formatter, the accessors are the expressions referencing the fields.
+fn debug_builder(name: &str, fields: &Fields, accessors: &[String]) -> String {
+	match fields {
+		Fields::Named(names) => {
+			let calls = names.iter().zip(accessors).map(|(field, accessor)| {
+				format!(".field({:?}, {accessor})", field.trim_start_matches("r#"))
+			}).collect::<String>();
+			format!("formatter.debug_struct({name:?}){calls}.finish()")
+		}
+		Fields::Unnamed(_) => {
+			let calls = accessors.iter().map(|accessor| format!(".field({accessor})")).collect::<String>();
+			format!("formatter.debug_tuple({name:?}){calls}.finish()")
+		}
+		Fields::Unit => format!("formatter.write_str({name:?})"),
+	}
+}
@@ -0,0 +1,333 @@
+use crate::ansi::{palette_tokens, parse_palette};
+use crate::helpers::CompileError;
+use ecc_ansi_lib_core::Palette;
+use proc_macro::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::iter::Peekable;
+
+// Parsing of struct and enum definitions for the derive macros.
+// Only the parts needed to implement formatting traits are parsed: attributes, name, generics and the field names.
+
+/// The parts of the type definition needed to generate an implementation.
+pub(crate) struct Definition {
+	/// Contents of the outer attributes, like 'ansi_debug(field = "c")'.
+	pub(crate) attributes: Vec<TokenStream>,
+	pub(crate) name: String,
+	/// Generic parameters with bounds but without defaults, like '<'a, T: Clone>'.
+	parameters: String,
+	/// Generic arguments, like '<'a, T>'.
+	arguments: String,
+	/// Where clause predicates, including the bounds added for type parameters.
+	predicates: Vec<String>,
+	pub(crate) body: Body,
+}
+
+pub(crate) enum Body {
+	Struct(Fields),
+	Enum(Vec<Variant>),
+}
+
+pub(crate) struct Variant {
+	pub(crate) attributes: Vec<TokenStream>,
+	pub(crate) name: String,
+	pub(crate) fields: Fields,
+}
+
+pub(crate) enum Fields {
+	Named(Vec<String>),
+	Unnamed(usize),
+	Unit,
+}
+
+impl Fields {
+	/// Pattern binding the fields of a struct or variant, named fields bind to 'field_name' and unnamed fields to 'field_0', 'field_1', ...
+	/// The prefix keeps fields from shadowing variables of the generated code (like a field named 'formatter').
+	pub(crate) fn pattern(&self) -> (String, Vec<String>) {
+		match self {
+			Fields::Named(names) => {
+				let bindings = names.iter().map(|name| format!("field_{}", name.trim_start_matches("r#"))).collect::<Vec<_>>();
+				let fields = names.iter().zip(&bindings).map(|(name, binding)| format!("{name}: {binding}")).collect::<Vec<_>>();
+				(format!("{{ {} }}", fields.join(", ")), bindings)
+			}
+			Fields::Unnamed(count) => {
+				let bindings = (0..*count).map(|index| format!("field_{index}")).collect::<Vec<_>>();
+				(format!("({})", bindings.join(", ")), bindings)
+			}
+			Fields::Unit => (String::new(), Vec::new()),
+		}
+	}
+}
+
+impl Definition {
+	/// Parses the struct or enum, which the derive macro is applied to.
+	/// The bound (like '::std::fmt::Debug') is added to all type parameters, like the standard derive macros do.
+	pub(crate) fn parse(input: TokenStream, derive: &str, bound: Option<&str>) -> Result<Definition, CompileError> {
+		let mut tokens = input.into_iter().peekable();
+		let attributes = parse_attributes(&mut tokens);
+		skip_visibility(&mut tokens);
+		let kind = match tokens.next() {
+			Some(TokenTree::Ident(ident)) if matches!(ident.to_string().as_str(), "struct" | "enum") => ident.to_string(),
+			Some(token) => return Err(CompileError::new(format!("{derive} can only be derived for structs and enums"), token.span())),
+			None => return Err(CompileError::new(format!("{derive} can only be derived for structs and enums"), Span::call_site())),
+		};
+		let Some(TokenTree::Ident(name)) = tokens.next() else {
+			return Err(CompileError::new(format!("Expected the name of the {kind}"), Span::call_site()));
+		};
+		let mut definition = Definition {
+			attributes,
+			name: name.to_string(),
+			parameters: String::new(),
+			arguments: String::new(),
+			predicates: Vec::new(),
+			body: Body::Struct(Fields::Unit),
+		};
+		
+		if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '<') {
+			tokens.next();
+			let mut depth = 1;
+			let mut generics = Vec::new();
+			for token in tokens.by_ref() {
+				if let TokenTree::Punct(punct) = &token {
+					match punct.as_char() {
+						'<' => depth += 1,
+						// Arrows ('->' of Fn types) do not close the generics.
+						'>' if !matches!(generics.last(), Some(TokenTree::Punct(previous)) if previous.as_char() == '-') => depth -= 1,
+						_ => {}
+					}
+				}
+				if depth == 0 {
+					break;
+				}
+				generics.push(token);
+			}
+			definition.parse_generics(generics, bound);
+		}
+		
+		// The body, where clauses are either before it (braces) or after it (tuple structs).
+		let mut body = None;
+		let mut where_clause = Vec::new();
+		for token in tokens {
+			match token {
+				TokenTree::Group(group) if body.is_none() && group.delimiter() != Delimiter::None && (group.delimiter() == Delimiter::Brace || where_clause.is_empty()) => body = Some(group),
+				TokenTree::Punct(punct) if punct.as_char() == ';' => {}
+				TokenTree::Ident(ident) if ident.to_string() == "where" => {}
+				token => where_clause.push(token),
+			}
+		}
+		definition.predicates.extend(split_top_level(where_clause).into_iter().map(|predicate| predicate.to_string()));
+		
+		definition.body = match (kind.as_str(), body) {
+			("struct", None) => Body::Struct(Fields::Unit),
+			("struct", Some(group)) => Body::Struct(parse_fields(&group)),
+			(_, Some(group)) => Body::Enum(parse_variants(&group)?),
+			(_, None) => return Err(CompileError::new(format!("Expected the variants of enum '{}'", definition.name), Span::call_site())),
+		};
+		Ok(definition)
+	}
+	
+	/// Splits the generics into parameters and arguments.
+	fn parse_generics(&mut self, generics: Vec<TokenTree>, bound: Option<&str>) {
+		let mut parameters = Vec::new();
+		let mut arguments = Vec::new();
+		for parameter in split_top_level(generics) {
+			let tokens = parameter.into_iter().collect::<Vec<_>>();
+			// Defaults ('T = u8') are only allowed in the definition.
+			let without_default = tokens.iter().take_while(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == '=')).cloned().collect::<TokenStream>();
+			let argument = match tokens.as_slice() {
+				[TokenTree::Punct(apostrophe), lifetime, ..] if apostrophe.as_char() == '\'' => format!("'{lifetime}"),
+				[TokenTree::Ident(keyword), name, ..] if keyword.to_string() == "const" => name.to_string(),
+				[name, ..] => {
+					if let Some(bound) = bound {
+						self.predicates.push(format!("{name}: {bound}"));
+					}
+					name.to_string()
+				}
+				[] => continue,
+			};
+			parameters.push(without_default.to_string());
+			arguments.push(argument);
+		}
+		self.parameters = format!("<{}>", parameters.join(", "));
+		self.arguments = format!("<{}>", arguments.join(", "));
+	}
+	
+	/// Implements a formatting trait (like '::std::fmt::Debug') with the body as content of the 'fmt(&self, formatter)' function.
+	pub(crate) fn implement(&self, trait_path: &str, body: &str) -> String {
+		let Definition { name, parameters, arguments, predicates, .. } = self;
+		let where_clause = if predicates.is_empty() { String::new() } else { format!("where {}", predicates.join(", ")) };
+		format!("
+			impl{parameters} {trait_path} for {name}{arguments} {where_clause} {{
+				fn fmt(&self, formatter: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{
+					{body}
+				}}
+			}}
+		")
+	}
+}
+
+/// Finds the attribute with the name (like 'ansi_debug') and returns its arguments. Repeated attributes are joined.
+pub(crate) fn find_attribute(attributes: &[TokenStream], name: &str) -> Result<Option<Group>, CompileError> {
+	let mut arguments: Option<Group> = None;
+	for attribute in attributes {
+		let mut tokens = attribute.clone().into_iter();
+		let Some(TokenTree::Ident(ident)) = tokens.next() else {
+			continue;
+		};
+		if ident.to_string() != name {
+			continue;
+		}
+		let Some(TokenTree::Group(group)) = tokens.next().filter(|token| matches!(token, TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis)) else {
+			return Err(CompileError::new(format!("Expected arguments in parentheses, like '#[{name}(...)]'"), ident.span()));
+		};
+		arguments = Some(match arguments {
+			None => group,
+			Some(previous) => {
+				let mut joined = previous.stream();
+				joined.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+				joined.extend(group.stream());
+				let mut joined = Group::new(Delimiter::Parenthesis, joined);
+				joined.set_span(group.span());
+				joined
+			}
+		});
+	}
+	Ok(arguments)
+}
+
+/// Argument of an attribute, like 'fmt = "{name}"'.
+pub(crate) type KeyValue = (Ident, Vec<TokenTree>);
+
+/// Splits the arguments of an attribute into 'key = value' pairs, the value are all tokens until the next comma.
+pub(crate) fn parse_key_values(arguments: &Group) -> Result<Vec<KeyValue>, CompileError> {
+	let mut pairs = Vec::new();
+	let mut tokens = arguments.stream().into_iter().peekable();
+	while let Some(token) = tokens.next() {
+		let TokenTree::Ident(key) = token else {
+			return Err(CompileError::new(format!("Expected a key like 'key = \"value\"', got '{token}'"), token.span()));
+		};
+		if !matches!(tokens.next(), Some(TokenTree::Punct(punct)) if punct.as_char() == '=') {
+			return Err(CompileError::new(format!("Expected '=' after '{key}'"), key.span()));
+		}
+		let mut value = Vec::new();
+		for token in tokens.by_ref() {
+			if matches!(&token, TokenTree::Punct(punct) if punct.as_char() == ',') {
+				break;
+			}
+			value.push(token);
+		}
+		if value.is_empty() {
+			return Err(CompileError::new(format!("Expected a value after '{key} ='"), key.span()));
+		}
+		pairs.push((key, value));
+	}
+	Ok(pairs)
+}
+
+/// Path of this crate in the generated code, unless changed with 'crate = path' (when the crate is renamed or only available as re-export).
+pub(crate) const DEFAULT_CRATE_PATH: &str = "::ecc_ansi_lib";
+
+/// Parses a path like 'crate = ::my_lib::ecc_ansi_lib', which replaces the default path of this crate in the generated code.
+pub(crate) fn parse_crate_path(key: &Ident, value: Vec<TokenTree>) -> Result<String, CompileError> {
+	if let Some(token) = value.iter().find(|token| !matches!(token, TokenTree::Ident(_) | TokenTree::Punct(_))) {
+		return Err(CompileError::new(format!("Expected a path like 'crate = ::ecc_ansi_lib', got '{token}'"), token.span()));
+	}
+	if !matches!(value.last(), Some(TokenTree::Ident(_))) {
+		return Err(CompileError::new("Expected a path like 'crate = ::ecc_ansi_lib', which ends with a name", key.span()));
+	}
+	Ok(value.into_iter().collect::<TokenStream>().to_string())
+}
+
+/// Parses a palette in braces, like 'palette = { @default custom 100 200 255 }'. Without palette the default palette is used.
+pub(crate) fn parse_palette_value(value: Option<KeyValue>) -> Result<Palette, CompileError> {
+	let Some((key, value)) = value else {
+		return parse_palette(&[("@".to_string(), Span::call_site()), ("default".to_string(), Span::call_site())], Span::call_site());
+	};
+	match value.as_slice() {
+		[TokenTree::Group(group)] if group.delimiter() == Delimiter::Brace => parse_palette(&palette_tokens(group.stream().into_iter())?, group.span()),
+		_ => Err(CompileError::new("Expected the palette in braces, like 'palette = { @default custom 100 200 255 }'", key.span())),
+	}
+}
+
+/// Contents of attributes like '#[serde(skip)]' or doc comments.
+fn parse_attributes<T: Iterator<Item = TokenTree>>(tokens: &mut Peekable<T>) -> Vec<TokenStream> {
+	let mut attributes = Vec::new();
+	while matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '#') {
+		tokens.next();
+		if let Some(TokenTree::Group(group)) = tokens.next() {
+			attributes.push(group.stream());
+		}
+	}
+	attributes
+}
+
+/// Skips 'pub', 'pub(crate)' and similar.
+fn skip_visibility<T: Iterator<Item = TokenTree>>(tokens: &mut Peekable<T>) {
+	if matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if ident.to_string() == "pub") {
+		tokens.next();
+		if matches!(tokens.peek(), Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis) {
+			tokens.next();
+		}
+	}
+}
+
+/// Splits tokens at commas, which are not inside of generics ('HashMap<K, V>'). Empty parts are dropped.
+/// Angle brackets after a top level '=' are operators of an expression (like the discriminant '= 1 << 2'), not generics.
+fn split_top_level(tokens: impl IntoIterator<Item = TokenTree>) -> Vec<TokenStream> {
+	let mut parts = vec![Vec::new()];
+	let mut depth = 0;
+	let mut in_expression = false;
+	for token in tokens {
+		if let TokenTree::Punct(punct) = &token {
+			let previous_is_dash = matches!(parts.last().and_then(|part| part.last()), Some(TokenTree::Punct(previous)) if previous.as_char() == '-');
+			match punct.as_char() {
+				'=' if depth == 0 => in_expression = true,
+				'<' if !in_expression => depth += 1,
+				'>' if !in_expression && !previous_is_dash => depth -= 1,
+				',' if depth == 0 => {
+					parts.push(Vec::new());
+					in_expression = false;
+					continue;
+				}
+				_ => {}
+			}
+		}
+		parts.last_mut().unwrap().push(token);
+	}
+	parts.into_iter().filter(|part| !part.is_empty()).map(|part| part.into_iter().collect()).collect()
+}
+
+/// Field names of a brace group, or the amount of fields of a parenthesis group.
+fn parse_fields(group: &Group) -> Fields {
+	let fields = split_top_level(group.stream());
+	match group.delimiter() {
+		Delimiter::Brace => Fields::Named(fields.into_iter().filter_map(|field| {
+			let mut tokens = field.into_iter().peekable();
+			parse_attributes(&mut tokens);
+			skip_visibility(&mut tokens);
+			tokens.next().map(|name| name.to_string())
+		}).collect()),
+		Delimiter::Parenthesis => Fields::Unnamed(fields.len()),
+		_ => Fields::Unit,
+	}
+}
+
+fn parse_variants(group: &Group) -> Result<Vec<Variant>, CompileError> {
+	let mut variants = Vec::new();
+	for variant in split_top_level(group.stream()) {
+		let mut tokens = variant.into_iter().peekable();
+		let attributes = parse_attributes(&mut tokens);
+		let Some(TokenTree::Ident(name)) = tokens.next() else {
+			return Err(CompileError::new("Expected the name of the enum variant", group.span()));
+		};
+		let fields = match tokens.next() {
+			Some(TokenTree::Group(fields)) => parse_fields(&fields),
+			// Unit variant, possibly with a discriminant ('= 1').
+			_ => Fields::Unit,
+		};
+		variants.push(Variant {
+			attributes,
+			name: name.to_string(),
+			fields,
+		});
+	}
+	Ok(variants)
+}
@@ -0,0 +1,227 @@
+use crate::arg_wrapper::{expect_highlight_colors, wrap_arguments, Highlights};
+use crate::definition::{find_attribute, parse_crate_path, parse_key_values, parse_palette_value, Body, Definition, Fields, KeyValue, DEFAULT_CRATE_PATH};
+use crate::helpers::{expect_string_literal, CompileError};
+use ecc_ansi_lib_core::Palette;
+use proc_macro::{Group, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::str::FromStr;
+
+// Derives 'Display' for structs and enums from «» format templates in '#[ansi(...)]' attributes.
+// Structs have the attribute on the type, enums on every variant. Keys of the attribute:
+// - 'fmt = "«y»{name}«» (#{id})"' the format, placeholders reference fields by name ('{name:>8}') or index ('{0}') of tuple fields.
+// - 'highlight', 'normal', 'debug' and 'number' wrap the placeholders with colors like 'arg_wrapper!()'.
+// - 'palette = { ... }' replaces the palette (only on the type), use '@default' to extend the default palette.
+// - 'crate = path' replaces the path of this crate in the generated code (only on the type, default '::ecc_ansi_lib').
+// Attributes of the enum type provide default values for its variants.
+// The format is rendered here plain, for the dark and for the light theme. Which one is used is decided at runtime.
+pub fn ansi_display_impl(input: TokenStream) -> TokenStream {
+	ansi_display(input).unwrap_or_else(|error| {
+		// Derives generate items, where compile_error!() must be followed by a semicolon.
+		let mut tokens = error.into_token_stream();
+		tokens.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+		tokens
+	})
+}
+
+/// The arguments of an '#[ansi(...)]' attribute.
+#[derive(Clone, Default)]
+struct Options {
+	format: Option<(String, Span)>,
+	highlight: Option<Vec<String>>,
+	normal: Option<String>,
+	debug: Option<String>,
+	number: Option<String>,
+	crate_path: Option<String>,
+}
+
+fn ansi_display(input: TokenStream) -> Result<TokenStream, CompileError> {
+	let definition = Definition::parse(input, "AnsiDisplay", None)?;
+	let (options, palette) = parse_options(find_attribute(&definition.attributes, "ansi")?, true)?;
+	let palette = parse_palette_value(palette)?;
+	
+	let body = match &definition.body {
+		Body::Struct(fields) => {
+			let Some(format) = &options.format else {
+				return Err(CompileError::new(format!("Expected the format of '{}', like '#[ansi(fmt = \"«y»{{name}}«»\")]'", definition.name), Span::call_site()));
+			};
+			let (pattern, write) = format_fields(format, &options, fields, &palette)?;
+			format!("let Self {pattern} = self;\n{write}")
+		}
+		Body::Enum(variants) if variants.is_empty() => "match *self {}".to_string(),
+		Body::Enum(variants) => {
+			if let Some((_, span)) = &options.format {
+				return Err(CompileError::new("The format of enums is provided per variant, add '#[ansi(fmt = \"...\")]' to every variant instead", *span));
+			}
+			let mut arms = String::new();
+			for variant in variants {
+				let variant_options = match find_attribute(&variant.attributes, "ansi")? {
+					Some(arguments) => merge(&options, parse_options(Some(arguments), false)?.0),
+					None => options.clone(),
+				};
+				let Some(format) = &variant_options.format else {
+					return Err(CompileError::new(format!("Expected the format of variant '{}', like '#[ansi(fmt = \"«y»{}«»\")]'", variant.name, variant.name), Span::call_site()));
+				};
+				let (pattern, write) = format_fields(format, &variant_options, &variant.fields, &palette)?;
+				arms.push_str(&format!("Self::{} {pattern} => {{ {write} }}\n", variant.name));
+			}
+			format!("match self {{ {arms} }}")
+		}
+	};
+	
+	let code = definition.implement("::std::fmt::Display", &body);
+	TokenStream::from_str(&code).map_err(|error| CompileError::new(format!("Could not generate Display implementation: {error:?}"), Span::call_site()))
+}
+
+/// Parses the arguments of an attribute, the palette is only allowed on the type.
+fn parse_options(arguments: Option<Group>, is_type: bool) -> Result<(Options, Option<KeyValue>), CompileError> {
+	let mut options = Options::default();
+	let mut palette = None;
+	let Some(arguments) = arguments else {
+		return Ok((options, palette));
+	};
+	for (key, value) in parse_key_values(&arguments)? {
+		let key_name = key.to_string();
+		let context = format!("Could not parse '{key_name}'");
+		let span = value[0].span();
+		let mut value_tokens = value.into_iter();
+		match key_name.as_str() {
+			"fmt" => options.format = Some((expect_string_literal(&mut value_tokens).map_err(|error| error.context(&context))?, span)),
+			"highlight" => options.highlight = Some(expect_highlight_colors(&mut value_tokens).map_err(|error| error.context(&context))?),
+			"normal" => options.normal = Some(expect_string_literal(&mut value_tokens).map_err(|error| error.context(&context))?),
+			"debug" => options.debug = Some(expect_string_literal(&mut value_tokens).map_err(|error| error.context(&context))?),
+			"number" => options.number = Some(expect_string_literal(&mut value_tokens).map_err(|error| error.context(&context))?),
+			"palette" if is_type => palette = Some((key.clone(), value_tokens.by_ref().collect())),
+			"palette" => return Err(CompileError::new("The palette can only be provided on the type, not per variant", key.span())),
+			"crate" if is_type => options.crate_path = Some(parse_crate_path(&key, value_tokens.by_ref().collect())?),
+			"crate" => return Err(CompileError::new("The crate path can only be provided on the type, not per variant", key.span())),
+			_ => return Err(CompileError::new(format!("Unknown key '{key_name}', expected one of: fmt, highlight, normal, debug, number, palette, crate"), key.span())),
+		}
+		if let Some(token) = value_tokens.next() {
+			return Err(CompileError::new(format!("Unexpected token '{token}' after the value of '{key_name}'"), token.span()));
+		}
+	}
+	Ok((options, palette))
+}
+
+/// Options of a variant, unset options are taken from the type.
+fn merge(defaults: &Options, options: Options) -> Options {
+	Options {
+		format: options.format,
+		highlight: options.highlight.or_else(|| defaults.highlight.clone()),
+		normal: options.normal.or_else(|| defaults.normal.clone()),
+		debug: options.debug.or_else(|| defaults.debug.clone()),
+		number: options.number.or_else(|| defaults.number.clone()),
+		crate_path: defaults.crate_path.clone(),
+	}
+}
+
+/// Renders the format for all palette versions and checks its placeholders against the fields.
+/// Returns the pattern binding the used fields and the code writing the format.
+fn format_fields(format: &(String, Span), options: &Options, fields: &Fields, palette: &Palette) -> Result<(String, String), CompileError> {
+	let (format, span) = format;
+	let error = |message: String| CompileError::new(message, *span);
+	
+	let wrapped = match &options.highlight {
+		Some(colors) => wrap_arguments(format, options.normal.as_deref().unwrap_or(""), &mut Highlights::new(colors, options.debug.as_deref(), options.number.as_deref())).map_err(error)?,
+		None if options.normal.is_some() || options.debug.is_some() || options.number.is_some() => {
+			return Err(error("The colors 'normal', 'debug' and 'number' require a 'highlight' color".to_string()));
+		}
+		None => format.clone(),
+	};
+	let versions = [palette.plain(), palette.clone(), palette.with_theme("light")].iter()
+		.map(|palette| ecc_ansi_lib_core::render(&wrapped, palette).map_err(|message| error(message.to_string())))
+		.collect::<Result<Vec<_>, _>>()?;
+	
+	// Placeholders reference fields, index placeholders of tuple fields are renamed to 'field_0'.
+	let mut used = Vec::new();
+	let versions = versions.iter().map(|version| rename_placeholders(version, &mut |name| {
+		let binding = match fields {
+			Fields::Named(names) if names.iter().any(|field| field == name) => name.to_string(),
+			Fields::Unnamed(count) if name.parse::<usize>().is_ok_and(|index| index < *count) => format!("field_{name}"),
+			_ if name.is_empty() => return Err(format!("Placeholders must reference a field, like '{{name}}' or '{{0}}', got '{{}}' in format '{format}'")),
+			_ => {
+				let fields = match fields {
+					Fields::Named(names) => names.join(", "),
+					Fields::Unnamed(count) => (0..*count).map(|index| index.to_string()).collect::<Vec<_>>().join(", "),
+					Fields::Unit => String::new(),
+				};
+				return Err(format!("Unknown field '{name}' in format '{format}', the fields are: [{fields}]"));
+			}
+		};
+		if !used.contains(&binding) {
+			used.push(binding.clone());
+		}
+		Ok(binding)
+	})).collect::<Result<Vec<_>, _>>().map_err(error)?;
+	
+	let pattern = match fields {
+		Fields::Named(_) => format!("{{ {} .. }}", used.iter().map(|name| format!("{name}: field_{name}, ")).collect::<String>()),
+		Fields::Unnamed(_) => {
+			let last = used.iter().filter_map(|name| name["field_".len()..].parse::<usize>().ok()).max();
+			let bindings = last.map_or(0, |last| last + 1);
+			let bindings = (0..bindings).map(|index| {
+				let binding = format!("field_{index}");
+				if used.contains(&binding) { binding } else { "_".to_string() }
+			}).collect::<Vec<_>>();
+			format!("({} ..)", bindings.iter().map(|binding| format!("{binding}, ")).collect::<String>())
+		}
+		Fields::Unit => String::new(),
+	};
+	let arguments = used.iter().map(|name| match fields {
+		Fields::Named(_) => format!(", {name} = field_{name}"),
+		_ => format!(", {name} = {name}"),
+	}).collect::<String>();
+	
+	let write = |version: &str| format!("::std::write!(formatter, \"{version}\"{arguments})");
+	let code = if versions[0] == versions[1] && versions[1] == versions[2] {
+		write(&versions[0])
+	} else {
+		let crate_path = options.crate_path.as_deref().unwrap_or(DEFAULT_CRATE_PATH);
+		format!("match {crate_path}::style_index() {{ 0 => {}, 1 => {}, _ => {} }}", write(&versions[0]), write(&versions[1]), write(&versions[2]))
+	};
+	Ok((pattern, code))
+}
+
+/// Calls the rename function with the argument name of every placeholder (also in width and precision like '{name:>width$}').
+/// The placeholders use the returned name instead. The format is still escaped like in a string literal.
+fn rename_placeholders(format: &str, rename: &mut impl FnMut(&str) -> Result<String, String>) -> Result<String, String> {
+	let mut output = String::with_capacity(format.len());
+	let mut rest = format;
+	while let Some(start) = rest.find(['{', '}']) {
+		output.push_str(&rest[..start]);
+		rest = &rest[start..];
+		if rest.starts_with("{{") || rest.starts_with("}}") {
+			output.push_str(&rest[..2]);
+			rest = &rest[2..];
+			continue;
+		}
+		if rest.starts_with('}') {
+			return Err(format!("Unmatched '}}' in format '{format}', use '}}}}' to print it"));
+		}
+		let Some(end) = rest.find('}') else {
+			return Err(format!("Unterminated placeholder in format '{format}', expected a closing '}}'"));
+		};
+		let placeholder = &rest[1..end];
+		let (name, specification) = placeholder.split_once(':').map_or((placeholder, None), |(name, specification)| (name, Some(specification)));
+		output.push('{');
+		output.push_str(&rename(name.trim())?);
+		if let Some(specification) = specification {
+			output.push(':');
+			// Width and precision can reference arguments, like 'width$'.
+			let mut parts = specification.split('$').peekable();
+			while let Some(part) = parts.next() {
+				if parts.peek().is_none() {
+					output.push_str(part);
+					break;
+				}
+				let reference_start = part.rfind(|char: char| !(char.is_alphanumeric() || char == '_')).map_or(0, |index| index + 1);
+				output.push_str(&part[..reference_start]);
+				output.push_str(&rename(&part[reference_start..])?);
+				output.push('$');
+			}
+		}
+		output.push('}');
+		rest = &rest[(end + 1)..];
+	}
+	output.push_str(rest);
+	Ok(output)
+}
@@ -1,4 +1,41 @@
-use proc_macro::{Delimiter, TokenTree};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::str::FromStr;
+
+/// An error, which is reported to the user as compile_error!() pointing at the offending tokens.
+pub(crate) struct CompileError {
+	message: String,
+	span: Span,
+}
+
+impl CompileError {
+	pub(crate) fn new(message: impl Into<String>, span: Span) -> CompileError {
+		CompileError {
+			message: message.into(),
+			span,
+		}
+	}
+	
+	/// Prepends context to the message.
+	pub(crate) fn context(mut self, context: &str) -> CompileError {
+		self.message = format!("{context}: {}", self.message);
+		self
+	}
+	
+	/// Generates 'compile_error!("message")' with every token using the span of the error.
+	pub(crate) fn into_token_stream(self) -> TokenStream {
+		let mut message = Literal::string(&self.message);
+		message.set_span(self.span);
+		let mut arguments = Group::new(Delimiter::Parenthesis, TokenStream::from(TokenTree::Literal(message)));
+		arguments.set_span(self.span);
+		let mut exclamation_mark = Punct::new('!', Spacing::Alone);
+		exclamation_mark.set_span(self.span);
+		TokenStream::from_iter([
+			TokenTree::Ident(Ident::new("compile_error", self.span)),
+			TokenTree::Punct(exclamation_mark),
+			TokenTree::Group(arguments),
+		])
+	}
+}
 
 /// Peeks the next chars, TRUE is returned when they match a provided string reference.
 pub(crate) fn matches_string<T: Iterator<Item = char> + Clone>(iterator: &T, matches: &str) -> bool {
@@ -18,21 +55,21 @@ pub(crate) fn matches_string<T: Iterator<Item = char> + Clone>(iterator: &T, mat
 	true
 }
 
-pub(crate) fn expect_string_literal<T: Iterator<Item = TokenTree>>(iterator: &mut T) -> Result<String, String> {
+pub(crate) fn expect_string_literal<T: Iterator<Item = TokenTree>>(iterator: &mut T) -> Result<String, CompileError> {
 	let mut token = match iterator.next() {
 		Some(token) => token,
-		None => return Err("Expected string literal argument (token), but there are no more arguments (tokens)".to_string()),
+		None => return Err(CompileError::new("Expected string literal argument (token), but there are no more arguments (tokens)", Span::call_site())),
 	};
 	
 	// Unwrap Group { delimiter: None } wrappings. Rust macros sometimes group a single token for reasons...
 	while let TokenTree::Group(group) = &token {
 		match group.delimiter() {
 			Delimiter::None => {},
-			_ => return Err(format!("Expected string literal argument (token), but encountered group with delimiter {:?}.", group.delimiter())),
+			_ => return Err(CompileError::new(format!("Expected string literal argument (token), but encountered group with delimiter {:?}.", group.delimiter()), group.span())),
 		}
 		let sub_tokens = group.stream().into_iter().collect::<Vec<_>>();
 		if sub_tokens.len() != 1 {
-			return Err("Expected string literal argument (token), but there was a Group".to_string());
+			return Err(CompileError::new("Expected string literal argument (token), but there was a Group", group.span()));
 		}
 		token = sub_tokens.into_iter().next().unwrap();
 	}
@@ -40,36 +77,85 @@ pub(crate) fn expect_string_literal<T: Iterator<Item = TokenTree>>(iterator: &mu
 	// Finally, expect the actual literal token.
 	let literal = match token {
 		TokenTree::Literal(literal) => literal,
-		_ => return Err(format!("Second argument must be a string literal. Got: {token:?}")),
+		_ => return Err(CompileError::new(format!("Argument must be a string literal. Got: '{token}'"), token.span())),
 	};
 	let string = literal.to_string();
 	
 	// Ensure it is a string literal.
 	if !string.starts_with('"') || !string.ends_with('"') {
-		panic!("Second argument must be a string literal, got >>{string}<<");
+		return Err(CompileError::new(format!("Argument must be a string literal, got >>{string}<<"), literal.span()));
 	}
 	
 	// Remove quotation & return.
 	Ok(string[1..(string.len() - 1)].to_string())
 }
 
-pub(crate) fn collect_first_argument<T: Iterator<Item = TokenTree>>(iterator: &mut T) -> String {
+pub(crate) fn collect_first_argument<T: Iterator<Item = TokenTree>>(iterator: &mut T) -> Result<TokenStream, CompileError> {
 	// Collect all tokens which are part of the first argument.
 	// Once a comma is encountered, the first argument is completed.
 	// Commas wrapped in any pair of brackets are not considered as they are part of TokenTree::Group sub-stream.
-	let mut format_string = String::new();
-	while let Some(token_tree) = iterator.next() {
+	let mut tokens = Vec::new();
+	for token_tree in iterator.by_ref() {
 		// Check if the current token is a comma - then return (as all argument-tokens had been gathered).
 		if let TokenTree::Punct(punct) = &token_tree {
 			if punct.as_char() == ',' {
-				return format_string;
+				return Ok(tokens.into_iter().collect());
 			}
 		}
-		// Every other token - add as string.
-		// Important here is to not add a spacer. Things will break like the '::' symbol if separated.
-		// TBI: Maybe some tokens cannot be concatenated like this. Valid Rust code however "should" be fine? Works for my test/use cases.
-		format_string.push_str(&token_tree.to_string());
+		tokens.push(token_tree);
+	}
+	
+	let argument = tokens.into_iter().collect::<TokenStream>();
+	Err(CompileError::new(format!("Expected first argument followed by a comma. No comma found. Collected argument so far is >>{argument}<<"), Span::call_site()))
+}
+
+/// Replaces every identifier with the name (also inside of groups) by the replacement tokens.
+/// Used to insert '$crate' into generated code, as it cannot be parsed from a string.
+pub(crate) fn replace_ident(tokens: TokenStream, name: &str, replacement: &TokenStream) -> TokenStream {
+	tokens.into_iter().flat_map(|token| match token {
+		TokenTree::Ident(ident) if ident.to_string() == name => replacement.clone(),
+		TokenTree::Group(group) => {
+			let mut replaced_group = Group::new(group.delimiter(), replace_ident(group.stream(), name, replacement));
+			replaced_group.set_span(group.span());
+			TokenStream::from(TokenTree::Group(replaced_group))
+		}
+		token => TokenStream::from(token),
+	}).collect()
+}
+
+/// Replaces the content of every string literal in the tokens (also inside of groups like macro calls).
+/// All other tokens are kept as they are, including their spans.
+/// The mapping function gets the content between the quotes (still escaped) and returns the new content.
+pub(crate) fn map_string_literals(tokens: TokenStream, map: &mut impl FnMut(&str) -> Result<String, String>) -> Result<TokenStream, CompileError> {
+	let mut output = Vec::new();
+	for token in tokens {
+		output.push(match token {
+			TokenTree::Group(group) => {
+				let mut mapped_group = Group::new(group.delimiter(), map_string_literals(group.stream(), map)?);
+				mapped_group.set_span(group.span());
+				TokenTree::Group(mapped_group)
+			}
+			TokenTree::Literal(literal) => TokenTree::Literal(map_string_literal(literal, map)?),
+			token => token,
+		});
+	}
+	Ok(output.into_iter().collect())
+}
+
+fn map_string_literal(literal: Literal, map: &mut impl FnMut(&str) -> Result<String, String>) -> Result<Literal, CompileError> {
+	let text = literal.to_string();
+	// String literals may have a prefix (like raw strings 'r#"'), but other literals (like chars) may also contain quotes.
+	let (Some(opener), Some(closer)) = (text.find('"'), text.rfind('"')) else {
+		return Ok(literal);
+	};
+	let (prefix, suffix) = (&text[..opener], &text[(closer + 1)..]);
+	if opener == closer || !prefix.chars().all(|char| char.is_ascii_alphabetic() || char == '#') || !suffix.chars().all(|char| char == '#') {
+		return Ok(literal);
 	}
 	
-	panic!("Expected first argument followed by a comma. No comma found. Collected argument so far is >>{format_string}<<");
+	let content = map(&text[(opener + 1)..closer]).map_err(|message| CompileError::new(message, literal.span()))?;
+	let mut mapped_literal = Literal::from_str(&format!("{prefix}\"{content}\"{suffix}"))
+		.map_err(|error| CompileError::new(format!("Could not create string literal: {error:?}"), literal.span()))?;
+	mapped_literal.set_span(literal.span());
+	Ok(mapped_literal)
 }
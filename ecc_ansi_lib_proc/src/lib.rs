@@ -3,7 +3,10 @@ use proc_macro::TokenStream;
 mod helpers;
 mod arg_wrapper;
 mod ansi;
-mod palette;
+mod palette_file;
+mod definition;
+mod debug_derive;
+mod display_derive;
 
 // TBI: Figure out if there is a better way to expose or re-expose macros on library level.
 
@@ -16,3 +19,18 @@ pub fn arg_wrapper_impl(input: TokenStream) -> TokenStream {
 pub fn ansi_impl(input: TokenStream) -> TokenStream {
 	ansi::ansi_impl(input)
 }
+
+#[proc_macro]
+pub fn ansi_palette_file_impl(input: TokenStream) -> TokenStream {
+	palette_file::ansi_palette_file_impl(input)
+}
+
+#[proc_macro_derive(AnsiDebug, attributes(ansi_debug))]
+pub fn ansi_debug(input: TokenStream) -> TokenStream {
+	debug_derive::ansi_debug_impl(input)
+}
+
+#[proc_macro_derive(AnsiDisplay, attributes(ansi))]
+pub fn ansi_display(input: TokenStream) -> TokenStream {
+	display_derive::ansi_display_impl(input)
+}
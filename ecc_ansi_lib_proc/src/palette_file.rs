@@ -0,0 +1,70 @@
+use crate::ansi::PALETTE_TEXT;
+use crate::helpers::{collect_first_argument, expect_string_literal, replace_ident, CompileError};
+use ecc_ansi_lib_core::Palette;
+use proc_macro::{Punct, Spacing, Span, TokenStream, TokenTree};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub fn ansi_palette_file_impl(input: TokenStream) -> TokenStream {
+	ansi_palette_file(input).unwrap_or_else(|error| {
+		// The macro is used in item position, where compile_error!() must be followed by a semicolon.
+		let mut tokens = error.into_token_stream();
+		tokens.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+		tokens
+	})
+}
+
+/// Defines a macro like `ansi_extend!()` named after the second argument, which uses the palette file as palette.
+/// The first argument is the path of this crate ('$crate' provided by the 'ansi_palette_file!()' wrapper), which the defined macro uses.
+/// The text of the palette is inserted into the defined macro, which splits it like `Palette::parse()`. The file is included as bytes, so that cargo rebuilds the crate (and expands this macro again) when the palette file changes.
+fn ansi_palette_file(input: TokenStream) -> Result<TokenStream, CompileError> {
+	let mut iterator = input.into_iter();
+	
+	let crate_path = collect_first_argument(&mut iterator)?;
+	let name = collect_first_argument(&mut iterator)?;
+	let name = match name.into_iter().collect::<Vec<_>>().as_slice() {
+		[TokenTree::Ident(ident)] => ident.to_string(),
+		_ => return Err(CompileError::new("Expected the name of the macro to define as first argument", Span::call_site())),
+	};
+	let span = iterator.clone().next().map_or_else(Span::call_site, |token| token.span());
+	let path = resolve_path(&expect_string_literal(&mut iterator)?, span)?;
+	if let Some(token) = iterator.find(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ',')) {
+		return Err(CompileError::new(format!("Unexpected token after the palette file path: '{token}'"), token.span()));
+	}
+	
+	// Validate the palette now, to report mistakes at the definition instead of at every usage.
+	let text = std::fs::read_to_string(&path)
+		.map_err(|error| CompileError::new(format!("Could not read palette file '{}': {error}", path.display()), span))?;
+	Palette::parse(&text).map_err(|error| CompileError::new(format!("Could not parse color palette file '{}': {error}", path.display()), span))?;
+	// The raw string literal keeps the text as it is, it needs more '#' than follow any quote in the text.
+	// Raw strings must not contain carriage returns, which are whitespaces for the tokenizer anyway.
+	let text = text.replace('\r', " ");
+	let hashes = "#".repeat(text.split('"').skip(1).map(|part| part.len() - part.trim_start_matches('#').len()).max().unwrap_or_default() + 1);
+	let palette = format!("{PALETTE_TEXT} r{hashes}\"{text}\"{hashes}");
+	
+	let path = format!("{:?}", path.display().to_string());
+	let code = format!("
+		const _: &[u8] = include_bytes!({path});
+		macro_rules! {name} {{
+			($format:expr) => {{
+				__ecc_ansi_lib::ansi_impl!($format, {palette})
+			}};
+			($format:expr, $( $palette:tt )*) => {{
+				__ecc_ansi_lib::ansi_impl!($format, {palette} $( $palette )*)
+			}};
+		}}
+	");
+	let code = TokenStream::from_str(&code).map_err(|error| CompileError::new(format!("Could not generate palette macro: {error:?}"), span))?;
+	Ok(replace_ident(code, "__ecc_ansi_lib", &crate_path))
+}
+
+/// Palette files are relative to the directory of the Cargo.toml of the crate using the macro.
+fn resolve_path(path: &str, span: Span) -> Result<PathBuf, CompileError> {
+	let path = PathBuf::from(path);
+	if path.is_absolute() {
+		return Ok(path);
+	}
+	let manifest_directory = std::env::var_os("CARGO_MANIFEST_DIR")
+		.ok_or_else(|| CompileError::new("Could not resolve palette file path, environment variable CARGO_MANIFEST_DIR is not set", span))?;
+	Ok(PathBuf::from(manifest_directory).join(path))
+}
@@ -0,0 +1,116 @@
+// Command-line tool, which renders «» markup to ANSI escape sequences. Lets shell scripts use the same palette as Rust programs:
+//   ecc_ansi "«r»Error:«» Something failed"
+//   echo "«g»Done«»" | ecc_ansi --palette colors.palette
+// Colors follow the same runtime policy as 'ansi_println!()' (NO_COLOR, CLICOLOR_FORCE, TTY detection, terminal capabilities and theme).
+
+use ecc_ansi_lib::{color_enabled, render, strip_ansi, terminal_caps, theme, ColorDepth, Palette, Stream, TerminalCaps, Theme};
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+use std::{env, fs};
+
+const USAGE: &str = "\
+Usage: ecc_ansi [OPTIONS] [MARKUP]...
+
+Renders «» markup (like '«r»Red«»') to ANSI escape sequences.
+The arguments are joined by spaces and printed with a line break, without arguments stdin is rendered.
+
+Options:
+  --palette FILE  Uses the palette of the file instead of the default palette (same format as 'ansi_palette_file!()')
+  --depth DEPTH   Reduces the colors to a depth of: truecolor, 256, 16 or 8 (default: detected from the terminal)
+  --no-color      Removes all color codes, like when the output is not a terminal
+  --strip         Removes ANSI escape sequences from the input, instead of rendering markup
+  --help          Prints this help
+";
+
+/// The parsed command-line arguments.
+#[derive(Default)]
+struct Options {
+	palette: Option<String>,
+	depth: Option<String>,
+	no_color: bool,
+	strip: bool,
+	markup: Vec<String>,
+}
+
+fn main() -> ExitCode {
+	let result = parse_arguments(env::args().skip(1)).and_then(|options| match options {
+		Some(options) => run(options),
+		None => {
+			print!("{USAGE}");
+			Ok(())
+		}
+	});
+	match result {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(message) => {
+			eprintln!("ecc_ansi: {message}");
+			ExitCode::FAILURE
+		}
+	}
+}
+
+/// Parses the arguments, returns None if the help is requested.
+fn parse_arguments(mut arguments: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+	let mut options = Options::default();
+	while let Some(argument) = arguments.next() {
+		match argument.as_str() {
+			"--help" | "-h" => return Ok(None),
+			"--palette" => options.palette = Some(arguments.next().ok_or("Expected a file after '--palette'")?),
+			"--depth" => options.depth = Some(arguments.next().ok_or("Expected a depth after '--depth' (truecolor, 256, 16 or 8)")?),
+			"--no-color" => options.no_color = true,
+			"--strip" => options.strip = true,
+			// Everything after '--' is markup, even if it looks like an option:
+			"--" => options.markup.extend(arguments.by_ref()),
+			_ if argument.starts_with("--") => return Err(format!("Unknown option '{argument}', see '--help'")),
+			_ => options.markup.push(argument),
+		}
+	}
+	Ok(Some(options))
+}
+
+fn run(options: Options) -> Result<(), String> {
+	let input = if !options.markup.is_empty() {
+		options.markup.join(" ") + "\n"
+	} else {
+		let mut input = String::new();
+		io::stdin().read_to_string(&mut input).map_err(|error| format!("Could not read stdin: {error}"))?;
+		input
+	};
+	
+	let output = if options.strip {
+		strip_ansi(&input).into_owned()
+	} else {
+		// The depth option replaces the detected depth, italics and links are still removed when the terminal does not support them:
+		let depth = match options.depth.as_deref() {
+			Some(name) => ColorDepth::from_name(name).ok_or_else(|| format!("Unknown color depth '{name}', expected one of: truecolor, 256, 16, 8"))?,
+			None => terminal_caps().depth,
+		};
+		let caps = TerminalCaps { depth, ..terminal_caps() };
+		let palette = load_palette(&options, depth)?;
+		let mut output = caps.reduce(&render(&input, &palette).map_err(|error| error.to_string())?).into_owned();
+		// Do not leave the terminal colored, when the last color code is not a reset:
+		if output.rfind("\u{1B}[").is_some_and(|start| !output[start..].starts_with("\u{1B}[m")) {
+			let line_break = output.len() - output.trim_end_matches('\n').len();
+			output.insert_str(output.len() - line_break, "\u{1B}[m");
+		}
+		output
+	};
+	let mut stdout = io::stdout().lock();
+	stdout.write_all(output.as_bytes()).and_then(|()| stdout.flush()).map_err(|error| format!("Could not write output: {error}"))
+}
+
+/// The palette with the depth and theme applied, or in plain mode when colors are disabled.
+fn load_palette(options: &Options, depth: ColorDepth) -> Result<Palette, String> {
+	let mut text = match &options.palette {
+		Some(path) => fs::read_to_string(path).map_err(|error| format!("Could not read palette file '{path}': {error}"))?,
+		None => "@default".to_string(),
+	};
+	text.push_str(&format!("\n@depth {}", depth.name()));
+	let palette = Palette::parse(&text).map_err(|error| match &options.palette {
+		Some(path) => format!("Invalid palette file '{path}': {error}"),
+		None => error.to_string(),
+	})?;
+	
+	let palette = if theme() == Theme::Light { palette.with_theme("light") } else { palette };
+	Ok(if options.no_color || !color_enabled(Stream::Stdout) { palette.plain() } else { palette })
+}
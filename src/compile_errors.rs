@@ -0,0 +1,79 @@
+// Mistakes in «» codes, palettes and derive attributes are compile errors. Each case is a 'compile_fail' doctest,
+// next to a version which compiles, to make sure the test fails for the mistake and not for the test code.
+
+/// Unknown colors are reported (with a suggestion of a similar palette key):
+/// ```
+/// let _ = ecc_ansi_lib::ansi!("«r»Text«»");
+/// ```
+/// ```compile_fail
+/// let _ = ecc_ansi_lib::ansi!("«redd»Text«»");
+/// ```
+pub struct UnknownColor;
+
+/// Unterminated codes are reported:
+/// ```
+/// let _ = ecc_ansi_lib::ansi!("«r»Text");
+/// ```
+/// ```compile_fail
+/// let _ = ecc_ansi_lib::ansi!("«r Text");
+/// ```
+pub struct UnterminatedCode;
+
+/// Unknown palette directives are reported:
+/// ```
+/// let _ = ecc_ansi_lib::ansi_extend!("«r»Text", @depth 256);
+/// ```
+/// ```compile_fail
+/// let _ = ecc_ansi_lib::ansi_extend!("«r»Text", @unknown 256);
+/// ```
+pub struct UnknownDirective;
+
+/// Invalid palette entries are reported:
+/// ```
+/// let _ = ecc_ansi_lib::ansi_extend!("«custom»Text", custom 255 0 0);
+/// ```
+/// ```compile_fail
+/// let _ = ecc_ansi_lib::ansi_extend!("«custom»Text", custom 256 0 0);
+/// ```
+pub struct InvalidPaletteEntry;
+
+/// Colors of `arg_wrapper!()` are checked:
+/// ```
+/// let _ = ecc_ansi_lib::arg_wrapper!("Hi {}", "c");
+/// ```
+/// ```compile_fail
+/// let _ = ecc_ansi_lib::arg_wrapper!("Hi {}", "cc");
+/// ```
+pub struct UnknownArgumentColor;
+
+/// Missing palette files are reported:
+/// ```compile_fail
+/// ecc_ansi_lib::ansi_palette_file!(team_ansi, "missing.palette");
+/// ```
+pub struct MissingPaletteFile;
+
+/// Placeholders of `#[derive(AnsiDisplay)]` must reference fields:
+/// ```
+/// #[derive(ecc_ansi_lib::AnsiDisplay)]
+/// #[ansi(fmt = "«y»{name}«»")]
+/// struct User { name: String }
+/// ```
+/// ```compile_fail
+/// #[derive(ecc_ansi_lib::AnsiDisplay)]
+/// #[ansi(fmt = "«y»{nmae}«»")]
+/// struct User { name: String }
+/// ```
+pub struct UnknownField;
+
+/// Keys of `#[ansi_debug(...)]` are checked:
+/// ```
+/// #[derive(ecc_ansi_lib::AnsiDebug)]
+/// #[ansi_debug(field = "y")]
+/// struct User { name: String }
+/// ```
+/// ```compile_fail
+/// #[derive(ecc_ansi_lib::AnsiDebug)]
+/// #[ansi_debug(fields = "y")]
+/// struct User { name: String }
+/// ```
+pub struct UnknownDebugKey;
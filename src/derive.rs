@@ -0,0 +1,165 @@
+use crate::policy::{color_enabled, theme, Stream, Theme};
+use std::cell::Cell;
+use std::fmt;
+
+// Runtime support of the derive macros, which generate the colored versions at compile time and choose one here.
+
+/// Color codes of the parts of Debug output, generated by `#[derive(AnsiDebug)]` for the dark and light theme.
+#[doc(hidden)]
+pub struct DebugStyle {
+	pub field: &'static str,
+	pub variant: &'static str,
+	pub string: &'static str,
+	pub number: &'static str,
+	pub punctuation: &'static str,
+}
+
+/// Debug implementation calling the closure, used to get the plain Debug output of a derived type.
+#[doc(hidden)]
+pub struct DebugFn<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result>(pub F);
+
+impl<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result> fmt::Debug for DebugFn<F> {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		(self.0)(formatter)
+	}
+}
+
+/// Colors the output of types deriving `AnsiDebug` or `AnsiDisplay`, which is plain without this wrapper (like in `format!()`, logs and assertion messages).
+/// The colors are only written when they are enabled for the stream (see `color_enabled()`): `println!("{:?}", Colored::new(&value))`.
+pub struct Colored<'a, T: ?Sized> {
+	value: &'a T,
+	stream: Stream,
+}
+
+impl<'a, T: ?Sized> Colored<'a, T> {
+	/// Colors the value for stdout.
+	pub fn new(value: &'a T) -> Self {
+		Colored { value, stream: Stream::Stdout }
+	}
+	
+	/// Colors the value for stderr, like for `eprintln!()`.
+	pub fn stderr(value: &'a T) -> Self {
+		Colored { value, stream: Stream::Stderr }
+	}
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for Colored<'_, T> {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let _request = request(Some(self.stream));
+		self.value.fmt(formatter)
+	}
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for Colored<'_, T> {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let _request = request(Some(self.stream));
+		self.value.fmt(formatter)
+	}
+}
+
+thread_local! {
+	/// Stream of the `Colored` value being formatted. Unset while the Debug output of a derived type is colored, so nested derived types print plain to not color twice.
+	static REQUESTED: Cell<Option<Stream>> = const { Cell::new(None) };
+}
+
+/// Restores the previously requested stream when dropped, also when formatting panics.
+struct Request(Option<Stream>);
+
+impl Drop for Request {
+	fn drop(&mut self) {
+		REQUESTED.set(self.0);
+	}
+}
+
+fn request(stream: Option<Stream>) -> Request {
+	Request(REQUESTED.replace(stream))
+}
+
+/// Which version of the text generated by `#[derive(AnsiDisplay)]` is written: 0 plain, 1 dark or 2 light theme.
+/// Plain unless the value is formatted inside `Colored` and colors are enabled for its stream (see `color_enabled()`), else depending on `theme()`.
+#[doc(hidden)]
+pub fn style_index() -> usize {
+	match REQUESTED.get() {
+		Some(stream) if color_enabled(stream) => if theme() == Theme::Light { 2 } else { 1 },
+		_ => 0,
+	}
+}
+
+/// Writes the Debug output of the value, colored when it is formatted inside `Colored` (see `style_index()`).
+#[doc(hidden)]
+pub fn debug_colored(formatter: &mut fmt::Formatter<'_>, styles: &[DebugStyle; 2], value: &dyn fmt::Debug) -> fmt::Result {
+	let style = match style_index() {
+		0 => return value.fmt(formatter),
+		1 => &styles[0],
+		_ => &styles[1],
+	};
+	
+	let mut plain = String::new();
+	let request = request(None);
+	if formatter.alternate() {
+		fmt::write(&mut plain, format_args!("{value:#?}"))?;
+	} else {
+		fmt::write(&mut plain, format_args!("{value:?}"))?;
+	}
+	drop(request);
+	
+	formatter.write_str(&colorize(&plain, style))
+}
+
+/// Colors the tokens of Debug output: Field names ('name:'), type and variant names (uppercase), strings, chars, numbers, booleans and brackets.
+fn colorize(text: &str, style: &DebugStyle) -> String {
+	let mut output = String::with_capacity(text.len() * 4);
+	let mut current = "";
+	let mut rest = text;
+	while let Some(char) = rest.chars().next() {
+		let (length, color) = match char {
+			'"' | '\'' => (quoted_length(rest, char), style.string),
+			'-' | '0'..='9' if rest.trim_start_matches('-').starts_with(|char: char| char.is_ascii_digit()) => {
+				(1 + rest[1..].find(|char: char| !(char.is_alphanumeric() || matches!(char, '.' | '_'))).unwrap_or(rest.len() - 1), style.number)
+			}
+			char if char.is_alphabetic() || char == '_' => {
+				let length = rest.find(|char: char| !(char.is_alphanumeric() || char == '_')).unwrap_or(rest.len());
+				let word = &rest[..length];
+				let after = rest[length..].trim_start_matches(' ');
+				let color = if after.starts_with(':') && !after.starts_with("::") {
+					style.field
+				} else if matches!(word, "true" | "false") || word.starts_with("inf") || word == "NaN" {
+					style.number
+				} else if char.is_uppercase() {
+					style.variant
+				} else {
+					""
+				};
+				(length, color)
+			}
+			'{' | '}' | '[' | ']' | '(' | ')' | ':' | ',' => (1, style.punctuation),
+			// Whitespace does not need a color change.
+			char if char.is_whitespace() => (char.len_utf8(), current),
+			char => (char.len_utf8(), ""),
+		};
+		if color != current {
+			output.push_str(if color.is_empty() { "\u{1B}[m" } else { color });
+			current = color;
+		}
+		output.push_str(&rest[..length]);
+		rest = &rest[length..];
+	}
+	if !current.is_empty() {
+		output.push_str("\u{1B}[m");
+	}
+	output
+}
+
+/// Length of a string or char literal including its quotes, escaped quotes do not end it.
+fn quoted_length(text: &str, quote: char) -> usize {
+	let mut escaped = false;
+	for (index, char) in text.char_indices().skip(1) {
+		match char {
+			_ if escaped => escaped = false,
+			'\\' => escaped = true,
+			char if char == quote => return index + 1,
+			_ => {}
+		}
+	}
+	text.len()
+}
@@ -1,5 +1,38 @@
 pub use ecc_ansi_lib_proc::ansi_impl;
 pub use ecc_ansi_lib_proc::arg_wrapper_impl;
+#[doc(hidden)]
+pub use ecc_ansi_lib_proc::ansi_palette_file_impl;
+/// Derives `Debug` like `#[derive(Debug)]`, but with colored field names, type and variant names, strings, numbers and punctuation.
+/// Pretty printing (`{:#?}`) is supported. The output is only colored inside `Colored` (like `println!("{:?}", Colored::new(&value))`), else plain.
+///
+/// The colors and the palette can be changed with the `ansi_debug` attribute, unspecified colors keep their defaults:
+/// `#[ansi_debug(field = "c", variant = "y", string = "g", number = "lb", punctuation = "gr", palette = { @default })]`
+///
+/// The generated code uses `::ecc_ansi_lib`, when the crate is renamed or re-exported provide its path: `#[ansi_debug(crate = ::my_lib::ecc_ansi_lib)]`.
+pub use ecc_ansi_lib_proc::AnsiDebug;
+/// Derives `Display` from «» templates, with placeholders referencing fields: `#[ansi(fmt = "«y»{name}«» (#{id})")]`.
+/// Structs have the attribute on the type, enums on every variant. Unknown fields are reported at compile time.
+/// The output is only colored inside `Colored` (like `println!("{}", Colored::new(&value))`), else plain. The theme is selected at runtime (see `theme()`).
+///
+/// Placeholders can be highlighted like with `arg_wrapper!()`: `#[ansi(fmt = "{name} has {count}", highlight = "c", normal = "w")]`.
+/// Also available are `debug` and `number`, and `palette = { @default ... }` to change the palette (only on the type).
+/// Attributes on the enum type apply to all variants.
+/// The generated code uses `::ecc_ansi_lib`, when the crate is renamed or re-exported provide its path on the type: `#[ansi(crate = ::my_lib::ecc_ansi_lib)]`.
+pub use ecc_ansi_lib_proc::AnsiDisplay;
+pub use ecc_ansi_lib_core::{ansi_to_html, ansi_to_markup, ansi_to_svg, hyperlink, render, strip_ansi, visible_width, ColorDepth, Error, Palette, SvgOptions};
+pub use policy::{color_enabled, set_color_override, set_theme_override, theme, Stream, Theme};
+pub use terminal::{set_terminal_caps_override, terminal_caps, TerminalCaps};
+pub use derive::Colored;
+#[doc(hidden)]
+pub use derive::{debug_colored, style_index, DebugFn, DebugStyle};
+
+#[cfg(doctest)]
+mod compile_errors;
+mod derive;
+mod policy;
+mod terminal;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Generates an RGB ANSI foreground color code.
 #[macro_export]
@@ -9,6 +42,14 @@ macro_rules! ansi_rgb {
 	};
 }
 
+/// Generates an RGB ANSI background color code.
+#[macro_export]
+macro_rules! ansi_bg_rgb {
+	($r:expr, $g:expr, $b:expr) => {
+		concat!("\u{1B}[48;2;", $r, ";", $g, ";", $b, "m")
+	};
+}
+
 /// Simply generates an ANSI reset code. Can be used in combination with concat!().
 #[macro_export]
 macro_rules! ansi_reset {
@@ -19,88 +60,40 @@ macro_rules! ansi_reset {
 
 /// This macro allows you to format «color» codes to ANSI colors using the default palette provided by this mod (see below).
 ///
-/// Create a different version of this macro if you like to use a different color palette.
+/// Use `define_palette!()` to generate a version of this macro with a different color palette.
+///
+/// `«+color»` remembers the current style before applying the color, `«-»` restores the remembered style (per string literal).
+/// `«link:https://example.com»Text«/link»` makes the text a clickable link (OSC 8).
+/// `«grad:r..y..b»Text«/grad»` and `«rainbow»Text«/rainbow»` color each character of the text.
+///
+/// The result is a string literal rendered at compile time, so it cannot follow `theme()` at runtime: it uses the dark theme, unless the palette contains `@select light`.
+/// Use `ansi_format!()` or the print macros (like `ansi_println!()`) for text, which follows the theme of the terminal.
 #[macro_export]
 macro_rules! ansi {
 	($format:expr) => {
 		// Essentially forward to 'ansi_escape' to prevent having to redefine the default color palette.
 		// But as extending with no additional palette colors works - this is fine.
-		ecc_ansi_lib::ansi_extend!($format,)
+		$crate::ansi_extend!($format,)
 	};
 }
 
 /// This macro is meant to be used to expand the default color palette with custom colors.
-/// It contains/defines the default palette.
+/// The default palette is defined in `ecc_ansi_lib_core/src/default.palette`.
+///
+/// Colors can also be aliases of other colors, like `warn = o`. Aliases follow the target, even if it is overridden later.
+///
+/// Besides colors the palette can contain directives:
+/// - `@depth 256` maps all colors to the nearest color of the given depth (`truecolor` (default), `256`, `16` or `8`).
+/// - `@default` inserts the default palette (which is what this macro does before your palette).
+/// - `@plain` removes all color codes, leaving the plain text.
+/// - `@override key ...` replaces an existing color (defining a color twice is an error otherwise).
+/// - `@remove key` removes a color.
+/// - `@theme light { key ... }` defines colors, which replace colors of the palette when the theme is active.
+/// - `@select light` activates a theme at compile time. Only the print macros (like `ansi_println!()`) select it at runtime, see `theme()`.
 #[macro_export]
 macro_rules! ansi_extend {
 	($format:expr, $( $palette:tt )*) => {
-		ecc_ansi_lib::ansi_impl!(
-			$format,
-			// Default color table:
-			// There probably are more scientific & correct methods to assign colors.
-			// This color palette was however created by the Ecconia's eye calibration...
-			
-			// Anyway, to make base colors darker prefix with 'd', to make them brighter/lighter prefix with 'l'.
-			
-			lr 255 100 100
-			r  255   0   0 // Red
-			dr 150   0   0
-			
-			lo 255 150 50
-			o  255 100  0 // Orange
-			do 150  40  0
-			
-			ly 255 255 120
-			y  255 255   0 // Yellow
-			dy 150 150   0
-			
-			la 160 255 80
-			a  130 255  0 // Acid
-			da  70 150  0
-			
-			lg 80 255 80
-			g   0 255  0 // Green
-			dg  0 150  0
-			
-			// I do not really have an idea what the color here is called.
-			// Or what it would be used for. My eyes are not trained for it.
-			
-			lc 120 255 255
-			c    0 255 255 // Cyan
-			dc   0 180 180
-			
-			// Also here no clue for this color.
-			
-			lb 50 120 255
-			b   0   0 255 // Blue
-			db  0   0 150
-			
-			lv 180 70 255
-			v  150  0 255 // Violet
-			dv 100  0 200
-			
-			lp 255 120 255
-			p  255   0 255 // Pink
-			dp 150   0 150
-			
-			lm 255 70 180
-			m  255  0 150 // Magenta
-			dm 200  0 100
-			
-			// Grayscale:
-			ds    0   0   0
-			s    20  20  20 // Black (DE: "Schwarz")
-			ls   30  30  30
-			dgr  60  60  60
-			gr  100 100 100 // Gray
-			lgr 150 150 150
-			dw  180 180 180
-			w   220 220 220 // White
-			lw  255 255 255
-			
-			// Append the extra values (or nothing):
-			$( $palette )*
-		)
+		$crate::ansi_impl!($format, @default $( $palette )*)
 	};
 }
 
@@ -108,7 +101,20 @@ macro_rules! ansi_extend {
 #[macro_export]
 macro_rules! ansi_replace {
 	($format:expr, $( $palette:tt )*) => {
-		ecc_ansi_lib::ansi_impl!($format, $( $palette )*)
+		$crate::ansi_impl!($format, $( $palette )*)
+	};
+}
+
+/// Defines a macro like `ansi_extend!()`, which uses a palette file instead of the default palette.
+/// The path is relative to the directory of your Cargo.toml, the crate is rebuilt when the file changes.
+/// The file uses the same format as the palette of `ansi_extend!()`, use `@default` in it to include the default palette.
+///
+/// `ansi_palette_file!(team_ansi, "colors.palette");` defines `team_ansi!("«warning»Careful!«»")`.
+#[macro_export]
+macro_rules! ansi_palette_file {
+	($( $arguments:tt )*) => {
+		// The defined macro needs the path of this crate, which only macro_rules can provide as '$crate'.
+		$crate::ansi_palette_file_impl!($crate, $( $arguments )*);
 	};
 }
 
@@ -117,17 +123,215 @@ macro_rules! ansi_replace {
 /// - arg_wrapper!("string literal or expression with {} arguments", "argument highlight color")
 /// - arg_wrapper!("string literal or expression with {} arguments", "argument highlight color", "normal text color")
 ///
-/// Create your own version of this macro to use a different version of ansi!() with a different palette.
+/// Each argument can have its own highlight color, either with a list of colors (in order of the arguments) or inline:
+/// - arg_wrapper!("{} of {}", ["y", "c"])
+/// - arg_wrapper!("{path:«y»} has {count:«c»} files", "w")
+///
+/// Debug (`{:?}`) and numeric (`{:x}`, `{:X}`, `{:o}`, `{:b}`, `{:e}`, `{:E}`) arguments can have their own color, provided by name after the other colors:
+/// - arg_wrapper!("{} is {:?} at {:#x}", "y", debug = "m", number = "c")
+///
+/// Use `define_palette!()` to generate a version of this macro with a different palette.
 #[macro_export]
 macro_rules! arg_wrapper {
-	($format:expr, $highlight:literal) => {
+	($format:expr, $( $colors:tt )+) => {
 		// It is important, that the ansi!() macro is an argument of arg_wrapper_impl!().
 		// As arg_wrapper_impl!() must be evaluated first, for ansi!() to colorize it's output.
 		// If ansi!() was first, then it would not find any color codes and colorize nothing.
-		ecc_ansi_lib::arg_wrapper_impl!(ecc_ansi_lib::ansi!($format), $highlight)
+		$crate::arg_wrapper_impl!($crate::ansi!($format), $( $colors )+)
+	};
+}
+
+/// Generates a set of macros using your own palette, so that you do not have to write them by hand:
+/// - `ansi` like `ansi_extend!()`: `my_ansi!("«custom»Text«»")`, also accepting additional palette tokens after the format.
+/// - `arg_wrapper` like `arg_wrapper!()`: `my_arg_wrapper!("Found {} files", "c")`.
+/// - Optionally print-like macros like `ansi_println!()`, named after the output macro: `print`, `println`, `eprint`, `eprintln` and `format`.
+///
+/// ```
+/// ecc_ansi_lib::define_palette!(
+///     #[macro_export] // Attributes are applied to all generated macros.
+///     my_ansi, my_arg_wrapper, { @default custom 100 200 255 },
+///     println: my_println, format: my_format,
+/// );
+/// println!(my_ansi!("«custom»Custom color«»"));
+/// my_println!(["custom"] "Found {} files", 42);
+/// ```
+///
+/// The palette is the same as for `ansi_replace!()`, use `@default` to include the default palette.
+#[macro_export]
+macro_rules! define_palette {
+	($( #[$meta:meta] )* $ansi:ident, $arg_wrapper:ident, $palette:tt $(, $output:ident: $name:ident )* $(,)?) => {
+		$crate::define_palette_macros!(@ansi ($) [ $( #[$meta] )* ] $ansi $arg_wrapper $palette);
+		$crate::define_palette_macros!(@outputs ($) [ $( #[$meta] )* ] $palette [ $( $output $name )* ]);
+	};
+}
+
+/// Implementation of `define_palette!()`.
+/// The '$' token is passed as '$d' to be able to write the repetitions and arguments of the generated macros.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! define_palette_macros {
+	(@ansi ($d:tt) $attributes:tt $ansi:ident $arg_wrapper:ident { $( $palette:tt )* }) => {
+		$crate::define_palette_macros!(@item $attributes
+			macro_rules! $ansi {
+				($d format:expr $d(, $d( $d extension:tt )* )?) => {
+					$crate::ansi_impl!($d format, $( $palette )* $d( $d( $d extension )* )?)
+				};
+			}
+		);
+		$crate::define_palette_macros!(@item $attributes
+			macro_rules! $arg_wrapper {
+				($d format:expr, $d( $d color:tt )+) => {
+					$crate::ansi_variant!({ $( $palette )* }, [ $d( $d color )+ ], $d format)
+				};
+			}
+		);
+	};
+	// The attributes and the palette are forwarded as single token, so that they can be used for every output macro.
+	(@outputs ($d:tt) $attributes:tt $palette:tt []) => {};
+	(@outputs ($d:tt) $attributes:tt $palette:tt [ $output:ident $name:ident $( $rest:tt )* ]) => {
+		$crate::define_palette_macros!(@output ($d) $attributes $output $name $palette);
+		$crate::define_palette_macros!(@outputs ($d) $attributes $palette [ $( $rest )* ]);
+	};
+	(@output ($d:tt) $attributes:tt print $name:ident $palette:tt) => {
+		$crate::define_palette_macros!(@print ($d) $attributes $name Stdout print $palette);
+	};
+	(@output ($d:tt) $attributes:tt println $name:ident $palette:tt) => {
+		$crate::define_palette_macros!(@print ($d) $attributes $name Stdout println $palette);
+	};
+	(@output ($d:tt) $attributes:tt eprint $name:ident $palette:tt) => {
+		$crate::define_palette_macros!(@print ($d) $attributes $name Stderr eprint $palette);
+	};
+	(@output ($d:tt) $attributes:tt eprintln $name:ident $palette:tt) => {
+		$crate::define_palette_macros!(@print ($d) $attributes $name Stderr eprintln $palette);
+	};
+	(@output ($d:tt) $attributes:tt format $name:ident $palette:tt) => {
+		$crate::define_palette_macros!(@print ($d) $attributes $name Stdout format $palette);
+	};
+	(@output ($d:tt) $attributes:tt $output:ident $name:ident $palette:tt) => {
+		compile_error!(concat!("Unknown output macro '", stringify!($output), "' for '", stringify!($name), "'. Expected one of: print, println, eprint, eprintln, format"));
+	};
+	(@print ($d:tt) $attributes:tt $name:ident $stream:ident $output:ident $palette:tt) => {
+		$crate::define_palette_macros!(@item $attributes
+			macro_rules! $name {
+				([ $d( $d color:tt )+ ] $d format:expr $d(, $d( $d arg:tt )* )?) => {
+					$crate::ansi_dispatch!($stream, $output, $palette, [ $d( $d color )+ ], $d format $d(, $d( $d arg )* )?)
+				};
+				($d format:expr $d(, $d( $d arg:tt )* )?) => {
+					$crate::ansi_dispatch!($stream, $output, $palette, [], $d format $d(, $d( $d arg )* )?)
+				};
+			}
+		);
+	};
+	// Not every project uses all generated macros, thus allow unused ones.
+	(@item [ $( #[$meta:meta] )* ] $item:item) => {
+		$( #[$meta] )*
+		#[allow(unused_macros)]
+		$item
+	};
+}
+
+/// Chooses at runtime between the colored, the light theme and the plain version of the format, depending on `color_enabled()` and `theme()`.
+/// The versions are generated at compile time and passed to the output macro (like `println`), the colored ones in true color.
+/// Used to implement the print-like macros below, the palette is provided in `{}` and arg_wrapper colors in `[]`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ansi_dispatch {
+	($stream:ident, $output:ident, { $( $palette:tt )* }, [ $( $color:tt )* ], $format:expr $(, $( $arg:tt )* )?) => {
+		if !$crate::color_enabled($crate::Stream::$stream) {
+			$output!($crate::ansi_variant!({ $( $palette )* @plain }, [ $( $color )* ], $format) $(, $( $arg )* )?)
+		} else if $crate::theme() == $crate::Theme::Light {
+			$crate::ansi_output!($output, $crate::ansi_variant!({ $( $palette )* @select light }, [ $( $color )* ], $format) $(, $( $arg )* )?)
+		} else {
+			$crate::ansi_output!($output, $crate::ansi_variant!({ $( $palette )* }, [ $( $color )* ], $format) $(, $( $arg )* )?)
+		}
+	};
+}
+
+/// Passes the colored format to the output macro, used by `ansi_dispatch!()`.
+/// Unless the terminal supports true color, italics and hyperlinks, the output is reduced to its capabilities first (see `TerminalCaps::reduce()`).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ansi_output {
+	($output:ident, $format:expr $(, $( $arg:tt )* )?) => {
+		match ::std::format_args!($format $(, $( $arg )* )?) {
+			arguments => match $crate::terminal_caps() {
+				caps if caps.depth == $crate::ColorDepth::TrueColor && caps.italic && caps.hyperlinks => $output!("{}", arguments),
+				caps => $output!("{}", caps.reduce(&arguments.to_string())),
+			},
+		}
+	};
+}
+
+/// Applies the palette to the format, and if colors are provided also wraps the arguments like `arg_wrapper!()`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! ansi_variant {
+	({ $( $palette:tt )* }, [], $format:expr) => {
+		$crate::ansi_impl!($format, $( $palette )*)
+	};
+	({ $( $palette:tt )* }, [ $( $color:tt )+ ], $format:expr) => {
+		$crate::arg_wrapper_impl!($crate::ansi_impl!($format, $( $palette )*), $( $color )+)
+	};
+}
+
+/// Like `print!()`, but with «color» codes of the default palette.
+/// Colors are only printed, when they are enabled for stdout (see `color_enabled()`). Else the plain text is printed.
+///
+/// Arguments can be highlighted like with `arg_wrapper!()`, by providing the colors in brackets before the format:
+/// `ansi_print!(["c", "w"] "Found {} files", count)`, also with a color per argument: `ansi_print!([["c", "y"]] "{} of {}", a, b)`
+#[macro_export]
+macro_rules! ansi_print {
+	([ $( $color:tt )+ ] $format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stdout, print, { @default }, [ $( $color )+ ], $format $(, $( $arg )* )?)
+	};
+	($format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stdout, print, { @default }, [], $format $(, $( $arg )* )?)
+	};
+}
+
+/// Like `println!()`, but with «color» codes of the default palette. See `ansi_print!()`.
+#[macro_export]
+macro_rules! ansi_println {
+	([ $( $color:tt )+ ] $format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stdout, println, { @default }, [ $( $color )+ ], $format $(, $( $arg )* )?)
+	};
+	($format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stdout, println, { @default }, [], $format $(, $( $arg )* )?)
+	};
+}
+
+/// Like `eprint!()`, but with «color» codes of the default palette.
+/// Colors are only printed, when they are enabled for stderr. See `ansi_print!()`.
+#[macro_export]
+macro_rules! ansi_eprint {
+	([ $( $color:tt )+ ] $format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stderr, eprint, { @default }, [ $( $color )+ ], $format $(, $( $arg )* )?)
+	};
+	($format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stderr, eprint, { @default }, [], $format $(, $( $arg )* )?)
+	};
+}
+
+/// Like `eprintln!()`, but with «color» codes of the default palette.
+/// Colors are only printed, when they are enabled for stderr. See `ansi_print!()`.
+#[macro_export]
+macro_rules! ansi_eprintln {
+	([ $( $color:tt )+ ] $format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stderr, eprintln, { @default }, [ $( $color )+ ], $format $(, $( $arg )* )?)
+	};
+	($format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stderr, eprintln, { @default }, [], $format $(, $( $arg )* )?)
+	};
+}
+
+/// Like `format!()`, but with «color» codes of the default palette.
+/// Colors are only added, when they are enabled for stdout. See `ansi_print!()`.
+#[macro_export]
+macro_rules! ansi_format {
+	([ $( $color:tt )+ ] $format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stdout, format, { @default }, [ $( $color )+ ], $format $(, $( $arg )* )?)
 	};
-	($format:expr, $highlight:literal, $normal:literal) => {
-		// Same as above ansi!() as argument.
-		ecc_ansi_lib::arg_wrapper_impl!(ecc_ansi_lib::ansi!($format), $highlight, $normal)
+	($format:expr $(, $( $arg:tt )* )?) => {
+		$crate::ansi_dispatch!(Stdout, format, { @default }, [], $format $(, $( $arg )* )?)
 	};
 }
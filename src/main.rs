@@ -1,9 +1,23 @@
-use ecc_ansi_lib::{ansi, ansi_extend, arg_wrapper};
+use ecc_ansi_lib::{ansi, ansi_extend, ansi_println, ansi_to_html, ansi_to_svg, arg_wrapper, render, AnsiDebug, Colored, Palette, SvgOptions};
+use std::env;
+use std::process::Command;
 
 fn main() {
+	// 'cargo run -- --html > examples.html' prints the examples as HTML page:
+	if env::args().any(|argument| argument == "--html") {
+		print_html();
+		return;
+	}
+	// 'cargo run -- --svg > default_colors.svg' renders the default colors as terminal screenshot:
+	if env::args().any(|argument| argument == "--svg") {
+		print!("{}", ansi_to_svg(DEFAULT_COLORS, &SvgOptions { window: true, title: "cargo run".to_string(), ..SvgOptions::default() }));
+		return;
+	}
+	
 	print_ansi_introduction();
 	print_default_colors();
 	print_arg_wrapper_introduction();
+	print_debug_introduction();
 }
 
 fn print_ansi_introduction() {
@@ -32,12 +46,22 @@ fn print_ansi_introduction() {
 		};
 	}
 	println!(ansi_replaced!("You can also start a new palette, which won't have support for the original colors ««r» <- would panic!() then, but custom «custom»colors«» work."));
+	// Color depth:
+	macro_rules! ansi_16 {
+		($format:expr) => {
+			ecc_ansi_lib::ansi_extend!($format, @depth 16)
+		};
+	}
+	println!(ansi_16!("Terminals without truecolor get the «o»nearest«» «lv»colors«» with '««@depth 16»' in the palette."));
 	println!();
 	
 	// Examples of color usage:
 	println!(ansi!("Color by RGB «80,255,80»««80,255,80»«»"));
+	println!(ansi!("Gradients with ««grad:lr..y..lc»: «grad:lr..y..lc»Loading complete«/grad» and ««rainbow»: «rainbow»Rainbow text«/rainbow»"));
 	println!(ansi!("Color by HEX «4FC5F8»««4FC5F8»«»"));
 	println!(ansi!("Color by color palette code «w»««w»«»"));
+	println!(ansi!("Background color «bg:db»««bg:db»«» or both «ly on db»««ly on db»«»"));
+	println!(ansi!("Text attributes «bold»««bold»«» can be combined «b,lo»««b,lo»«/b» and disabled «u»««/b»«/u» individually «»(««/u»)."));
 	println!();
 	
 	// Examples of expanding the color palette:
@@ -49,11 +73,22 @@ fn print_ansi_introduction() {
 		var var_a var_b 180
 	));
 	println!();
+	
+	// Runtime rendering:
+	println!(ansi!("Links work in «u»most«» terminals: «link:https://github.com/Ecconia/RustEccAnsi»«lc»RustEccAnsi«»«/link»"));
+	let runtime_text = format!("Text only known at «{}»runtime«» can be colored with '«lo»render()«»'.", "c");
+	println!("{}", render(&runtime_text, &Palette::default()).unwrap());
+	
+	// Runtime color policy:
+	ansi_println!("Use '«lo»ansi_println!()«»' to only print «r»colors«», when the output is a terminal (try piping this into a file).");
+	ansi_println!(["c"] "It also can wrap arguments like {}, and honors {} and {}.", "arg_wrapper!()", "NO_COLOR", "CLICOLOR_FORCE");
+	println!();
 }
 
-fn print_default_colors() {
-	println!("Default color palette:");
-	println!(ansi!(concat!("Bright:",
+// Also rendered as image by 'cargo run -- --svg > default_colors.svg'.
+const DEFAULT_COLORS: &str = concat!(
+	"Default color palette:\n",
+	ansi!(concat!("Bright:",
 		" «lr»Red",
 		" «lo»Orange",
 		" «ly»Yellow",
@@ -66,8 +101,8 @@ fn print_default_colors() {
 		" «lm»Magenta",
 		" «lr»Red",
 		" «»",
-	)));
-	println!(ansi!(concat!("Colors:",
+	)), "\n",
+	ansi!(concat!("Colors:",
 		" «r»Red",
 		" «o»Orange",
 		" «y»Yellow",
@@ -80,8 +115,8 @@ fn print_default_colors() {
 		" «m»Magenta",
 		" «r»Red",
 		" «»",
-	)));
-	println!(ansi!(concat!("  Dark:",
+	)), "\n",
+	ansi!(concat!("  Dark:",
 		" «dr»Red",
 		" «do»Orange",
 		" «dy»Yellow",
@@ -94,9 +129,8 @@ fn print_default_colors() {
 		" «dm»Magenta",
 		" «dr»Red",
 		" «»",
-	)));
-	
-	println!(ansi!(concat!("Grayscale:",
+	)), "\n",
+	ansi!(concat!("Grayscale:",
 		" «ds»BLACK",
 		" «s»BLACK",
 		" «ls»BLACK",
@@ -107,8 +141,11 @@ fn print_default_colors() {
 		" «w»WHITE",
 		" «lw»WHITE",
 		"«»"
-	)));
-	println!();
+	)), "\n",
+);
+
+fn print_default_colors() {
+	println!("{DEFAULT_COLORS}");
 }
 
 fn print_arg_wrapper_introduction() {
@@ -150,3 +187,43 @@ fn print_arg_wrapper_introduction() {
 		), "p" // Highlight color pink
 	), "concat!()", "arg_wrapper!()");
 }
+
+#[derive(AnsiDebug)]
+struct Example {
+	name: &'static str,
+	count: u32,
+	tags: Vec<Tag>,
+}
+
+#[derive(AnsiDebug)]
+enum Tag {
+	Important,
+	Priority(u8),
+}
+
+fn print_debug_introduction() {
+	println!();
+	ansi_println!(["c", "w"] "Types deriving {} print colored Debug output inside {} (when colors are enabled):", "AnsiDebug", "Colored");
+	let example = Example { name: "Demo", count: 3, tags: vec![Tag::Important, Tag::Priority(2)] };
+	println!("{:?}", Colored::new(&example));
+	println!("{:#?}", Colored::new(&example));
+}
+
+/// Runs this program again with forced (dark theme truecolor) colors and prints its output converted to HTML.
+fn print_html() {
+	let output = Command::new(env::current_exe().expect("Could not locate this program"))
+		.env("CLICOLOR_FORCE", "1")
+		.env("COLORTERM", "truecolor")
+		.env_remove("NO_COLOR")
+		.env_remove("COLORFGBG")
+		.output()
+		.expect("Could not run this program again");
+	let text = String::from_utf8_lossy(&output.stdout);
+	println!("<!DOCTYPE html>");
+	println!("<html>");
+	println!("<head><meta charset=\"utf-8\"><title>Ecc Ansi Lib examples</title></head>");
+	println!("<body style=\"background-color:#000000;color:#e5e5e5\">");
+	println!("<pre>{}</pre>", ansi_to_html(&text));
+	println!("</body>");
+	println!("</html>");
+}
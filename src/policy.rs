@@ -0,0 +1,114 @@
+use std::env;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// Output stream, for which the color policy is decided.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stream {
+	Stdout,
+	Stderr,
+}
+
+/// Background of the terminal, which decides the theme of the palette (see `@theme` palette sections).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+	Dark,
+	Light,
+}
+
+impl Theme {
+	/// Name of the theme in palettes, like in '@theme light { ... }'.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Theme::Dark => "dark",
+			Theme::Light => "light",
+		}
+	}
+}
+
+const OVERRIDE_NONE: u8 = 0;
+const OVERRIDE_ENABLED: u8 = 1;
+const OVERRIDE_DISABLED: u8 = 2;
+const THEME_LIGHT: u8 = 1;
+const THEME_DARK: u8 = 2;
+
+static OVERRIDE: AtomicU8 = AtomicU8::new(OVERRIDE_NONE);
+static STDOUT_ENABLED: OnceLock<bool> = OnceLock::new();
+static STDERR_ENABLED: OnceLock<bool> = OnceLock::new();
+static THEME_OVERRIDE: AtomicU8 = AtomicU8::new(OVERRIDE_NONE);
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Overrides the process-wide color policy from code. `None` restores the detected policy.
+pub fn set_color_override(enabled: Option<bool>) {
+	OVERRIDE.store(match enabled {
+		None => OVERRIDE_NONE,
+		Some(true) => OVERRIDE_ENABLED,
+		Some(false) => OVERRIDE_DISABLED,
+	}, Ordering::Relaxed);
+}
+
+/// Whether colors should be written to the stream.
+/// Unless overridden with `set_color_override()`, this is decided once per stream in this order:
+/// - `NO_COLOR` is set (and not empty) => disabled
+/// - `CLICOLOR_FORCE` is set (and not empty or `0`) => enabled
+/// - `CLICOLOR` is `0` => disabled
+/// - `TERM` is `dumb` => disabled
+/// - Otherwise enabled, when the stream is a terminal.
+pub fn color_enabled(stream: Stream) -> bool {
+	match OVERRIDE.load(Ordering::Relaxed) {
+		OVERRIDE_ENABLED => return true,
+		OVERRIDE_DISABLED => return false,
+		_ => {}
+	}
+	match stream {
+		Stream::Stdout => *STDOUT_ENABLED.get_or_init(|| detect(std::io::stdout().is_terminal())),
+		Stream::Stderr => *STDERR_ENABLED.get_or_init(|| detect(std::io::stderr().is_terminal())),
+	}
+}
+
+fn detect(is_terminal: bool) -> bool {
+	let variable = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+	
+	if variable("NO_COLOR").is_some() {
+		return false;
+	}
+	if variable("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+		return true;
+	}
+	if variable("CLICOLOR").is_some_and(|value| value == "0") {
+		return false;
+	}
+	if variable("TERM").is_some_and(|value| value == "dumb") {
+		return false;
+	}
+	is_terminal
+}
+
+/// Overrides the process-wide theme from code. `None` restores the detected theme.
+pub fn set_theme_override(theme: Option<Theme>) {
+	THEME_OVERRIDE.store(match theme {
+		None => OVERRIDE_NONE,
+		Some(Theme::Light) => THEME_LIGHT,
+		Some(Theme::Dark) => THEME_DARK,
+	}, Ordering::Relaxed);
+}
+
+/// The theme, which the print macros apply to the palette.
+/// Unless overridden with `set_theme_override()`, the theme is detected once from `COLORFGBG` (like '15;0', set by some terminals).
+/// Without hint the dark theme is used, as most terminals have a dark background.
+pub fn theme() -> Theme {
+	match THEME_OVERRIDE.load(Ordering::Relaxed) {
+		THEME_LIGHT => return Theme::Light,
+		THEME_DARK => return Theme::Dark,
+		_ => {}
+	}
+	*THEME.get_or_init(|| env::var("COLORFGBG").ok().and_then(|value| theme_from_colorfgbg(&value)).unwrap_or(Theme::Dark))
+}
+
+/// `COLORFGBG` contains the foreground and background color index ('fg;bg' or 'fg;default;bg').
+/// Of the 16 basic colors, white (7) and bright colors except for gray (8) are light backgrounds.
+fn theme_from_colorfgbg(value: &str) -> Option<Theme> {
+	let background = value.rsplit(';').next()?.parse::<u8>().ok()?;
+	Some(if background == 7 || (9..=15).contains(&background) { Theme::Light } else { Theme::Dark })
+}
@@ -0,0 +1,191 @@
+use ecc_ansi_lib_core::ColorDepth;
+use std::borrow::Cow;
+use std::env;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// Features supported by the terminal, see `terminal_caps()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TerminalCaps {
+	pub depth: ColorDepth,
+	pub italic: bool,
+	/// Clickable links (OSC 8), like `«link:url»`.
+	pub hyperlinks: bool,
+}
+
+static CAPS: OnceLock<TerminalCaps> = OnceLock::new();
+static CAPS_OVERRIDE: Mutex<Option<TerminalCaps>> = Mutex::new(None);
+
+/// Overrides the process-wide terminal capabilities from code. `None` restores the detected capabilities.
+pub fn set_terminal_caps_override(caps: Option<TerminalCaps>) {
+	*CAPS_OVERRIDE.lock().unwrap_or_else(|error| error.into_inner()) = caps;
+}
+
+/// Capabilities of the terminal, detected once from the environment (see `TerminalCaps::from_environment()`).
+/// The print macros (like `ansi_println!()`) reduce the colors to its depth, unless overridden with `set_terminal_caps_override()`.
+pub fn terminal_caps() -> TerminalCaps {
+	if let Some(caps) = *CAPS_OVERRIDE.lock().unwrap_or_else(|error| error.into_inner()) {
+		return caps;
+	}
+	*CAPS.get_or_init(|| TerminalCaps::from_environment(|name| env::var(name).ok()))
+}
+
+/// Terminals known to support truecolor, italics and hyperlinks, by `TERM_PROGRAM` or a part of `TERM`.
+const MODERN_PROGRAMS: &[&str] = &["iTerm.app", "WezTerm", "vscode", "ghostty", "Hyper", "rio"];
+const MODERN_TERMS: &[&str] = &["kitty", "alacritty", "foot", "wezterm", "ghostty", "rio", "contour"];
+
+impl TerminalCaps {
+	/// Reduces the escape sequences of the text to what the terminal supports: Colors are mapped to the nearest color of its depth,
+	/// italics and hyperlinks (keeping the link text) are removed if not supported.
+	/// The print macros (like `ansi_println!()`) apply this to their output.
+	pub fn reduce<'a>(&self, text: &'a str) -> Cow<'a, str> {
+		ecc_ansi_lib_core::reduce_ansi(text, self.depth, self.italic, self.hyperlinks)
+	}
+	
+	/// Detects the capabilities from the environment variables provided by the function, in this order:
+	/// - `COLORTERM` is `truecolor` or `24bit` => truecolor
+	/// - `TERM_PROGRAM` or `TERM` name a terminal known to support everything (like `WezTerm` or `xterm-kitty`)
+	/// - `TERM` ends with `-direct` or `-truecolor` => truecolor, or contains `256color` => 256 colors
+	/// - The compiled terminfo entry of `TERM` (`colors`, `RGB`/`Tc` and `sitm` for italics), searched in `TERMINFO`,
+	///   `~/.terminfo`, `TERMINFO_DIRS`, `/etc/terminfo`, `/lib/terminfo` and `/usr/share/terminfo`.
+	/// - Otherwise 16 colors, or 8 without `TERM`.
+	///
+	/// Hyperlinks are only reported for known terminals, VTE terminals (`VTE_VERSION`) and Windows Terminal (`WT_SESSION`).
+	pub fn from_environment(variable: impl Fn(&str) -> Option<String>) -> TerminalCaps {
+		let variable = |name: &str| variable(name).filter(|value| !value.is_empty());
+		let term = variable("TERM").unwrap_or_default();
+		let modern = variable("TERM_PROGRAM").is_some_and(|program| MODERN_PROGRAMS.contains(&program.as_str()))
+			|| MODERN_TERMS.iter().any(|name| term.contains(name))
+			|| variable("WT_SESSION").is_some();
+		let vte = variable("VTE_VERSION").and_then(|version| version.parse::<u32>().ok()).is_some_and(|version| version >= 5000);
+		let terminfo = if term.is_empty() { None } else { Terminfo::find(&term, &variable) };
+		
+		let depth = if variable("COLORTERM").is_some_and(|value| value == "truecolor" || value == "24bit") || modern
+			|| term.ends_with("-direct") || term.ends_with("-truecolor") {
+			ColorDepth::TrueColor
+		} else if term.contains("256color") {
+			ColorDepth::Colors256
+		} else if let Some(terminfo) = &terminfo {
+			terminfo.depth()
+		} else if term.is_empty() {
+			ColorDepth::Colors8
+		} else {
+			ColorDepth::Colors16
+		};
+		TerminalCaps {
+			depth,
+			italic: modern || vte || terminfo.as_ref().is_some_and(|terminfo| terminfo.italic),
+			hyperlinks: modern || vte,
+		}
+	}
+}
+
+/// The capabilities of a compiled terminfo entry, which are relevant for colors.
+struct Terminfo {
+	/// The 'colors' number capability.
+	colors: Option<u32>,
+	/// The 'RGB' or 'Tc' extended capability, announcing truecolor support.
+	rgb: bool,
+	/// The 'sitm' (enter italics mode) string capability.
+	italic: bool,
+}
+
+/// Index of the 'colors' number and the 'sitm' string in compiled terminfo entries.
+const COLORS_INDEX: usize = 13;
+const ITALIC_INDEX: usize = 311;
+
+impl Terminfo {
+	fn find(term: &str, variable: &impl Fn(&str) -> Option<String>) -> Option<Terminfo> {
+		let mut directories = Vec::new();
+		directories.extend(variable("TERMINFO").map(PathBuf::from));
+		directories.extend(variable("HOME").map(|home| PathBuf::from(home).join(".terminfo")));
+		directories.extend(variable("TERMINFO_DIRS").iter().flat_map(|dirs| dirs.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from)));
+		directories.extend(["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"].map(PathBuf::from));
+		
+		// Entries are in a directory named after the first character, or its hex code on macOS.
+		let first = term.chars().next()?;
+		directories.iter()
+			.flat_map(|directory| [directory.join(first.to_string()).join(term), directory.join(format!("{:x}", first as u32)).join(term)])
+			.find_map(|path| std::fs::read(path).ok())
+			.and_then(|data| Terminfo::parse(&data))
+	}
+	
+	/// Parses the compiled terminfo format (see 'man term'): Header, names, booleans, numbers, string offsets and the string table.
+	/// Followed by an optional extended section with named capabilities (like 'RGB').
+	fn parse(data: &[u8]) -> Option<Terminfo> {
+		let short = |offset: usize| data.get(offset..(offset + 2)).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]));
+		let number_size = match short(0)? {
+			0o432 => 2,
+			0o1036 => 4,
+			_ => return None,
+		};
+		let [names_size, bools_count, numbers_count, strings_count, table_size] = [1, 2, 3, 4, 5].map(|index| short(index * 2).map_or(0, |value| value.max(0) as usize));
+		let read_number = |offset: usize| -> Option<i32> {
+			let bytes = data.get(offset..(offset + number_size))?;
+			Some(if number_size == 2 { i16::from_le_bytes([bytes[0], bytes[1]]) as i32 } else { i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) })
+		};
+		
+		let mut offset = 12 + names_size + bools_count;
+		offset += offset % 2;
+		let numbers_start = offset;
+		let colors = (COLORS_INDEX < numbers_count).then(|| read_number(numbers_start + COLORS_INDEX * number_size)).flatten()
+			.filter(|colors| *colors >= 0).map(|colors| colors as u32);
+		offset += numbers_count * number_size;
+		let italic = ITALIC_INDEX < strings_count && short(offset + ITALIC_INDEX * 2).is_some_and(|string| string >= 0);
+		offset += strings_count * 2 + table_size;
+		offset += offset % 2;
+		
+		Some(Terminfo {
+			colors,
+			rgb: Terminfo::extended_booleans(data, offset, number_size).iter().any(|name| name == "RGB" || name == "Tc"),
+			italic,
+		})
+	}
+	
+	/// Names of the set booleans of the extended section at the offset.
+	fn extended_booleans(data: &[u8], offset: usize, number_size: usize) -> Vec<String> {
+		let short = |offset: usize| data.get(offset..(offset + 2)).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]));
+		let count = |offset: usize| short(offset).map(|value| value.max(0) as usize);
+		let (Some(bools_count), Some(numbers_count), Some(strings_count)) = (count(offset), count(offset + 2), count(offset + 4)) else {
+			return Vec::new();
+		};
+		let bools_start = offset + 10;
+		let mut position = bools_start + bools_count;
+		position += position % 2;
+		position += numbers_count * number_size;
+		// Offsets of the string values, followed by the offsets of all names.
+		let values_offsets = position;
+		let names_offsets = values_offsets + strings_count * 2;
+		let table_start = names_offsets + (bools_count + numbers_count + strings_count) * 2;
+		let string_at = |start: usize| data.get(start..).and_then(|rest| rest.iter().position(|byte| *byte == 0).map(|length| &rest[..length]));
+		// The names follow the string values in the table.
+		let mut names_start = table_start;
+		for index in 0..strings_count {
+			let Some(value_offset) = short(values_offsets + index * 2).filter(|value_offset| *value_offset >= 0) else {
+				continue;
+			};
+			let start = table_start + value_offset as usize;
+			if let Some(value) = string_at(start) {
+				names_start = names_start.max(start + value.len() + 1);
+			}
+		}
+		
+		(0..bools_count)
+			.filter(|index| data.get(bools_start + index) == Some(&1))
+			.filter_map(|index| {
+				let name_offset = short(names_offsets + index * 2).filter(|name_offset| *name_offset >= 0)?;
+				string_at(names_start + name_offset as usize).map(|name| String::from_utf8_lossy(name).into_owned())
+			})
+			.collect()
+	}
+	
+	fn depth(&self) -> ColorDepth {
+		match self.colors.unwrap_or(0) {
+			_ if self.rgb => ColorDepth::TrueColor,
+			0x1000000.. => ColorDepth::TrueColor,
+			256.. => ColorDepth::Colors256,
+			16.. => ColorDepth::Colors16,
+			_ => ColorDepth::Colors8,
+		}
+	}
+}
@@ -0,0 +1,96 @@
+// Snapshot tests of colored output, enabled by the 'testing' feature.
+// The output is compared as «» markup (see 'ansi_to_markup()'), which keeps snapshot files and failure messages readable.
+
+use crate::{ansi_to_markup, Palette};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Environment variable, which makes `assert_ansi_snapshot!()` write the actual output into the snapshot files instead of comparing.
+pub const UPDATE_VARIABLE: &str = "UPDATE_ANSI_SNAPSHOTS";
+
+/// Asserts that colored output matches a snapshot, both compared as «» markup. On mismatch the test fails with a line diff.
+///
+/// `assert_ansi_snapshot!("name", output)` compares with the file `tests/snapshots/name.snap` of your crate.
+/// Run the tests with `UPDATE_ANSI_SNAPSHOTS=1` to create or update the snapshot files.
+/// Colors are named by the default palette, provide another palette as third argument: `assert_ansi_snapshot!("name", output, &palette)`.
+///
+/// `assert_ansi_snapshot!(output, @"«r»Expected«»")` compares with inline markup instead.
+#[macro_export]
+macro_rules! assert_ansi_snapshot {
+	($output:expr, @ $expected:literal $(,)?) => {
+		$crate::testing::assert_markup(&$output, $expected, &$crate::Palette::default())
+	};
+	($name:literal, $output:expr $(,)?) => {
+		$crate::testing::assert_snapshot(env!("CARGO_MANIFEST_DIR"), $name, &$output, &$crate::Palette::default())
+	};
+	($name:literal, $output:expr, $palette:expr $(,)?) => {
+		$crate::testing::assert_snapshot(env!("CARGO_MANIFEST_DIR"), $name, &$output, $palette)
+	};
+}
+
+/// Compares the output with the snapshot file 'tests/snapshots/{name}.snap' in the crate directory, see `assert_ansi_snapshot!()`.
+#[track_caller]
+pub fn assert_snapshot(crate_directory: &str, name: &str, output: &str, palette: &Palette) {
+	let directory = Path::new(crate_directory).join("tests").join("snapshots");
+	let path = directory.join(format!("{name}.snap"));
+	let actual = ansi_to_markup(output, palette);
+	
+	if env::var_os(UPDATE_VARIABLE).is_some_and(|value| value != "0") {
+		fs::create_dir_all(&directory).unwrap_or_else(|error| panic!("Could not create snapshot directory '{}': {error}", directory.display()));
+		fs::write(&path, format!("{actual}\n")).unwrap_or_else(|error| panic!("Could not write snapshot '{}': {error}", path.display()));
+		return;
+	}
+	let Ok(expected) = fs::read_to_string(&path) else {
+		panic!("Snapshot '{}' does not exist, run the tests with {UPDATE_VARIABLE}=1 to create it. Actual output:\n{actual}", path.display());
+	};
+	// Snapshot files end with a line break and might have been checked out with Windows line breaks.
+	let expected = expected.replace("\r\n", "\n");
+	let expected = expected.strip_suffix('\n').unwrap_or(&expected);
+	if actual != expected {
+		panic!("Snapshot '{}' does not match (- expected, + actual), run the tests with {UPDATE_VARIABLE}=1 to update it:\n{}", path.display(), diff(expected, &actual));
+	}
+}
+
+/// Compares the output with the expected markup, see `assert_ansi_snapshot!()`.
+#[track_caller]
+pub fn assert_markup(output: &str, expected: &str, palette: &Palette) {
+	let actual = ansi_to_markup(output, palette);
+	if actual != expected {
+		panic!("Output does not match the expected markup (- expected, + actual):\n{}", diff(expected, &actual));
+	}
+}
+
+/// Line diff of two texts, lines are prefixed with '-' (only expected), '+' (only actual) or ' ' (both).
+fn diff(expected: &str, actual: &str) -> String {
+	let expected = expected.split('\n').collect::<Vec<_>>();
+	let actual = actual.split('\n').collect::<Vec<_>>();
+	// Length of the longest common subsequence of the remaining lines, starting at both indices:
+	let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+	for a in (0..expected.len()).rev() {
+		for b in (0..actual.len()).rev() {
+			common[a][b] = if expected[a] == actual[b] {
+				common[a + 1][b + 1] + 1
+			} else {
+				common[a + 1][b].max(common[a][b + 1])
+			};
+		}
+	}
+	
+	let mut output = String::new();
+	let (mut a, mut b) = (0, 0);
+	while a < expected.len() || b < actual.len() {
+		if a < expected.len() && b < actual.len() && expected[a] == actual[b] {
+			output.push_str(&format!(" {}\n", expected[a]));
+			a += 1;
+			b += 1;
+		} else if a == expected.len() || (b < actual.len() && common[a][b + 1] > common[a + 1][b]) {
+			output.push_str(&format!("+{}\n", actual[b]));
+			b += 1;
+		} else {
+			output.push_str(&format!("-{}\n", expected[a]));
+			a += 1;
+		}
+	}
+	output
+}
@@ -14,6 +14,7 @@ mod ansi {
 				println!("Input: '{}'", stringify!($input));
 				let result = ansi_impl!($input, $( $palette )*);
 				println!("Result: '{}'", result.replace("\u{1B}", "\\u{1B}"));
+				println!("Markup: '{}'", ecc_ansi_lib::ansi_to_markup(result, &ecc_ansi_lib::Palette::default()));
 				println!("Expect: '{}'", $expect);
 				println!(ansi_impl!($input, $( $palette )*));
 				assert_eq!(result, $expect);
@@ -59,6 +60,130 @@ mod ansi {
 		r ff0000
 	);
 	
+	// Background colors, with every color input format:
+	ansi_test!(background_only
+		in "«bg:r»Hi!«»«bg:0000ff»«bg:1,2,3»",
+		ex "\u{1B}[48;2;255;0;0mHi!\u{1B}[m\u{1B}[48;2;0;0;255m\u{1B}[48;2;1;2;3m"
+		r ff0000
+	);
+	ansi_test!(foreground_on_background
+		in "«r on b»Hi!«»«00ff00 on 1, 2, 3»«on r»",
+		ex "\u{1B}[38;2;255;0;0;48;2;0;0;255mHi!\u{1B}[m\u{1B}[38;2;0;255;0;48;2;1;2;3m\u{1B}[48;2;255;0;0m"
+		r ff0000
+		b 0 0 255
+	);
+	
+	// Attributes, combined with colors:
+	ansi_test!(attributes_with_colors
+		in "«b,u,r»Hi!«»«bold underline lr»Hi!«»«i on b, 1,2,3»",
+		ex "\u{1B}[1;4;38;2;255;0;0mHi!\u{1B}[m\u{1B}[1;4;38;2;255;100;100mHi!\u{1B}[m\u{1B}[3;48;2;0;0;255;38;2;1;2;3m"
+		r ff0000
+		lr 255 100 100
+		b 0 0 255
+	);
+	// A single item prefers the color, 'fg:' always uses the color:
+	ansi_test!(attributes_color_precedence
+		in "«b»«i»«fg:b,d»",
+		ex "\u{1B}[38;2;0;0;255m\u{1B}[3m\u{1B}[38;2;0;0;255;2m"
+		b 0 0 255
+	);
+	ansi_test!(attributes_disabling
+		in "«b,u,r»Bold«/b»Underlined«/u,/fg»Plain",
+		ex "\u{1B}[1;4;38;2;255;0;0mBold\u{1B}[22mUnderlined\u{1B}[24;39mPlain"
+		r ff0000
+		b 0 0 255
+	);
+	
+	// Color depth, colors are mapped to the nearest available color:
+	ansi_test!(depth_256
+		in "«r on b»Hi!«»«gr»«a»",
+		ex "\u{1B}[38;5;196;48;5;21mHi!\u{1B}[m\u{1B}[38;5;241m\u{1B}[38;5;118m"
+		r ff0000
+		b 0 0 255
+		gr 100 100 100
+		a 130 255 0
+		@depth 256
+	);
+	ansi_test!(depth_16
+		in "«r on b»Hi!«»«gr»«dr»",
+		ex "\u{1B}[91;44mHi!\u{1B}[m\u{1B}[90m\u{1B}[31m"
+		r ff0000
+		dr 150 0 0
+		b 0 0 255
+		gr 100 100 100
+		@depth 16
+	);
+	ansi_test!(depth_8
+		in "«r on b»Hi!«»«b,lw»",
+		ex "\u{1B}[31;44mHi!\u{1B}[m\u{1B}[1;37m"
+		r ff0000
+		b 0 0 255
+		lw 255 255 255
+		@depth 8
+	);
+	ansi_test!(color_functions
+		in "«hsl(0, 100%, 50%)»«sky»«hsv(120 100% 100%) on oklch(0 0 0)»",
+		ex "\u{1B}[38;2;255;0;0m\u{1B}[38;2;90;163;237m\u{1B}[38;2;0;255;0;48;2;0;0;0m"
+		sky hsl(210, 80%, 64%)
+	);
+	ansi_test!(alias_palette_entry
+		in "«warn»«error on warn»",
+		ex "\u{1B}[38;2;0;0;255m\u{1B}[38;2;0;255;0;48;2;0;0;255m"
+		warn = o
+		error = lr
+		o ff8000
+		lr 00ff00
+		@override o 0000ff // Aliases follow later changes.
+	);
+	ansi_test!(remove_palette_entry
+		in "«r»",
+		ex "\u{1B}[38;2;0;0;255m"
+		@default
+		@remove r
+		r 0 0 255
+	);
+	ansi_test!(theme_palette_section
+		in "«warn»«bg»",
+		ex "\u{1B}[38;2;150;80;0m\u{1B}[38;2;255;255;255m"
+		o ff8000
+		warn = o
+		bg 0 0 0
+		@theme light {
+			o 150 80 0 // Aliases follow theme colors.
+			bg = lw
+		}
+		lw ffffff
+		@select light
+	);
+	ansi_test!(gradient
+		in "«grad:r..b»Hi {{!«/grad» «rainbow»ab«/rainbow»",
+		ex "\u{1B}[38;2;255;0;0mH\u{1B}[38;2;179;79;128mi \u{1B}[38;2;101;78;194m{{\u{1B}[38;2;0;0;255m!\u{1B}[m \u{1B}[38;2;255;130;113ma\u{1B}[38;2;228;134;223mb\u{1B}[m"
+		r ff0000
+		b 0000ff
+	);
+	ansi_test!(gradient_keeps_style
+		in "«y»«grad:r..r»a\n««b«/grad»c",
+		ex "\u{1B}[38;2;255;255;0m\u{1B}[38;2;255;0;0ma\n«b\u{1B}[0;38;2;255;255;0mc"
+		r ff0000
+		y ffff00
+	);
+	ansi_test!(hyperlink
+		in "«link:https://example.com/docs?a=1»«u»Docs«»«/link»",
+		ex "\u{1B}]8;;https://example.com/docs?a=1\u{07}\u{1B}[4mDocs\u{1B}[m\u{1B}]8;;\u{07}"
+	);
+	ansi_test!(style_stack
+		in "«r»outer «+y»inner«-» still red«»",
+		ex "\u{1B}[38;2;255;0;0mouter \u{1B}[38;2;255;255;0minner\u{1B}[0;38;2;255;0;0m still red\u{1B}[m"
+		r ff0000
+		y ffff00
+	);
+	ansi_test!(style_stack_nested
+		in "«+r on y»a«+b»b«+/b,y»c«-»d«-»e«-»f",
+		ex "\u{1B}[38;2;255;0;0;48;2;255;255;0ma\u{1B}[1mb\u{1B}[22;38;2;255;255;0mc\u{1B}[0;38;2;255;0;0;48;2;255;255;0;1md\u{1B}[0;38;2;255;0;0;48;2;255;255;0me\u{1B}[mf"
+		r ff0000
+		y ffff00
+	);
+	
 	// Not sure what else to test, this one is much more simplistic than arg_wrapper.
 }
 
@@ -70,14 +195,14 @@ mod arg_wrapper {
 	macro_rules! arg_wrapper_test {
 		(
 			$name:ident
-			in $input:expr, $highlight:literal, $normal:literal
+			in $input:expr, $highlight:tt, $normal:literal
 			ex $expect:literal
 			$( ,$arg:literal )*
 		) => {
 			#[test]
 			fn $name() {
 				println!("Input: '{}'", stringify!($input));
-				println!("With color options: '{}' / '{}'", $highlight, $normal);
+				println!("With color options: '{}' / '{}'", stringify!($highlight), $normal);
 				let result = arg_wrapper_impl!($input, $highlight, $normal);
 				println!("Result: '{}'", result);
 				println!("Expect: '{}'", $expect);
@@ -87,14 +212,14 @@ mod arg_wrapper {
 		};
 		(
 			$name:ident
-			in $input:expr, $highlight:literal
+			in $input:expr, $highlight:tt
 			ex $expect:literal
 			$( ,$arg:literal )*
 		) => {
 			#[test]
 			fn $name() {
 				println!("Input: '{}'", stringify!($input));
-				println!("With color options: '{}'", $highlight);
+				println!("With color options: '{}'", stringify!($highlight));
 				let result = arg_wrapper_impl!($input, $highlight);
 				println!("Result: '{}'", result);
 				println!("Expect: '{}'", $expect);
@@ -193,4 +318,567 @@ mod arg_wrapper {
 		ex "«»{{«highlight»{}«»}}\"{{«highlight»{}«»}}"
 		, "arg1", "arg2"
 	);
+	
+	// Per-argument colors, the last color is used for all remaining arguments.
+	// Bordering arguments only skip the highlight, when it is the same color.
+	arg_wrapper_test!(color_list_normal
+		in "{} of {}{}{}", ["y", "c"], "n"
+		ex "«y»{}«n» of «c»{}{}{}«»"
+		, "arg1", "arg2", "arg3", "arg4"
+	);
+	arg_wrapper_test!(inline_colors_default
+		in "{:«y»}{0:«c»>5} {:?}", "h"
+		ex "«y»{}«c»{0:>5}«» «h»{:?}«»"
+		, "arg1", "arg2"
+	);
+	
+	// Debug and numeric arguments use the named colors, inline colors still win.
+	#[test]
+	fn kind_colors() {
+		assert_eq!(arg_wrapper_impl!("{} {:?} {:#?} {0:>8x?}", "h", debug = "d"), "«h»{}«» «d»{:?}«» «d»{:#?}«» «d»{0:>8x?}«»");
+		assert_eq!(arg_wrapper_impl!("{}{:x}{:#010b}{:e}{:E}{:o}", "h", "n", number = "c"), "«h»{}«c»{:x}{:#010b}{:e}{:E}{:o}«»");
+		assert_eq!(arg_wrapper_impl!("{:?} {:x>5} {:«y»?}", ["h", "i"], debug = "d", number = "c",), "«d»{:?}«» «i»{:x>5}«» «y»{:?}«»");
+	}
+}
+
+#[cfg(test)]
+mod render {
+	use ecc_ansi_lib::{render, Palette};
+	
+	#[test]
+	fn default_palette() {
+		let name = "Ecconia";
+		let result = render(&format!("«r»{name}«» ««»"), &Palette::default()).unwrap();
+		assert_eq!(result, "\u{1B}[38;2;255;0;0mEcconia\u{1B}[m «»");
+	}
+	
+	#[test]
+	fn custom_palette() {
+		let palette = Palette::parse("
+			@default // Keep the default colors.
+			max = 255
+			custom max 0 max
+		").unwrap();
+		let result = render("«custom»Hi«» «r on 0,0,255»", &palette).unwrap();
+		assert_eq!(result, "\u{1B}[38;2;255;0;255mHi\u{1B}[m \u{1B}[38;2;255;0;0;48;2;0;0;255m");
+		
+		// Without '@default' only the custom colors are known:
+		let palette = Palette::parse("custom ff00ff @depth 256").unwrap();
+		assert_eq!(render("«custom»", &palette).unwrap(), "\u{1B}[38;5;201m");
+		assert!(render("«r»", &palette).is_err());
+		
+		// Colors can also be defined with color functions:
+		let palette = Palette::parse("sky hsl(210deg, 0.8, 64%) dark oklch(0 0 0)").unwrap();
+		assert_eq!(render("«sky on dark»", &palette).unwrap(), "\u{1B}[38;2;90;163;237;48;2;0;0;0m");
+		
+		// Themes are applied at runtime:
+		let palette = Palette::parse("@default @theme light { custom 0 0 0 } custom 255 255 255").unwrap();
+		assert_eq!(render("«custom»", &palette).unwrap(), "\u{1B}[38;2;255;255;255m");
+		assert_eq!(render("«custom»«lw»", &palette.with_theme("light")).unwrap(), "\u{1B}[38;2;0;0;0m\u{1B}[38;2;0;0;0m");
+		assert_eq!(render("«custom»", &palette.with_theme("unknown")).unwrap(), "\u{1B}[38;2;255;255;255m");
+	}
+	
+	#[test]
+	fn errors() {
+		let palette = Palette::default();
+		assert_eq!(render("«unknown»", &palette).unwrap_err().message(), "Could not parse ANSI color format: 'unknown' (in 'unknown').");
+		assert_eq!(render("«b,dyy»", &palette).unwrap_err().message(), "Could not parse ANSI color format: 'dyy' (in 'b,dyy'). Did you mean 'dy'?");
+		assert!(render("«r", &palette).is_err());
+		assert!(render("«/r»", &palette).is_err());
+		assert!(Palette::parse("custom 256 0 0").is_err());
+		assert_eq!(Palette::parse("max = 255 custom mx 0 0").unwrap_err().token(), Some(4));
+		assert!(Palette::parse("@depth 42").is_err());
+		assert!(Palette::parse("custom hsl(400, 50%, 50%)").is_err());
+		assert!(render("«hsl(0, 100%)»", &palette).is_err());
+		assert!(render("«+r»a«-»b«-»", &palette).is_err());
+		assert!(render("«grad:r»a«/grad»", &palette).is_err());
+		assert!(render("«grad:r..xyz»a«/grad»", &palette).is_err());
+		assert!(render("«grad:r..b»a«y»b«/grad»", &palette).is_err());
+		assert!(render("«rainbow»a", &palette).is_err());
+		assert!(render("«link:»a«/link»", &palette).is_err());
+		
+		// Colors must not be replaced by accident:
+		assert!(Palette::parse("@default r 0 0 255").is_err());
+		assert!(Palette::parse("custom 0 0 255 @default custom ff0000").is_err());
+		assert!(Palette::parse("@override custom 0 0 255").is_err());
+		assert!(Palette::parse("@remove custom").is_err());
+		assert!(Palette::parse("@default @override r").is_err());
+		// Broken aliases:
+		assert_eq!(Palette::parse("warn = oo oo ff8000 @remove oo").unwrap_err().token(), Some(2));
+		assert_eq!(Palette::parse("@default warn = dyy").unwrap_err().message(), "Alias 'warn' refers to unknown color 'dyy'. Did you mean 'dy'?");
+		assert!(Palette::parse("a = b b = a").is_err());
+		// Theme colors must also exist outside of the theme:
+		assert!(Palette::parse("@theme light { custom 0 0 255 }").is_err());
+		assert!(Palette::parse("@theme light custom 0 0 255").is_err());
+		assert!(render("«link:a b»a«/link»", &palette).is_err());
+	}
+	
+	#[test]
+	fn gradient() {
+		let palette = Palette::parse("r ff0000 b 0000ff @depth 8").unwrap();
+		// Format arguments and escape sequences stay intact, repeated colors are skipped:
+		let result = render("«grad:r..r»{name}\u{1B}[1mab«/grad»", &palette).unwrap();
+		assert_eq!(result, "\u{1B}[31m{name}\u{1B}[1mab\u{1B}[m");
+	}
+}
+
+#[cfg(test)]
+mod policy {
+	use super::debug_derive::{Custom, Point, Renamed, Shape};
+	use super::display_derive::{Pair, RenamedDisplay, Status, User};
+	use ecc_ansi_lib::{ansi_format, define_palette, set_color_override, set_terminal_caps_override, set_theme_override, ColorDepth, Colored, TerminalCaps, Theme};
+	use std::fmt;
+	use std::panic;
+	
+	define_palette!(team_ansi, team_arg_wrapper, { custom 100 200 255 }, format: team_format);
+	
+	struct Panicking;
+	
+	impl fmt::Debug for Panicking {
+		fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+			panic!("Formatting failed");
+		}
+	}
+	
+	// Single test, as the override is process-wide and tests run in parallel.
+	#[test]
+	fn color_override() {
+		let name = "Ecconia";
+		set_color_override(Some(true));
+		let truecolor = TerminalCaps { depth: ColorDepth::TrueColor, italic: true, hyperlinks: true };
+		set_terminal_caps_override(Some(truecolor));
+		assert_eq!(ansi_format!("«r»{}«» ««»", name), "\u{1B}[38;2;255;0;0mEcconia\u{1B}[m «»");
+		assert_eq!(ansi_format!(["c"] "Hi {}!", name), "\u{1B}[mHi \u{1B}[38;2;0;255;255mEcconia\u{1B}[m!");
+		assert_eq!(team_format!(["custom"] "{}", name), "\u{1B}[38;2;100;200;255mEcconia\u{1B}[m");
+		assert_eq!(ansi_format!([["r", "b"]] "{}{}", 1, 2), "\u{1B}[38;2;255;0;0m1\u{1B}[38;2;0;0;255m2\u{1B}[m");
+		assert_eq!(ansi_format!(["r", debug = "b"] "{}{:?}", 1, 2), "\u{1B}[38;2;255;0;0m1\u{1B}[38;2;0;0;255m2\u{1B}[m");
+		assert_eq!(format!("{:?}", Colored::new(&Shape::Line(1, -2))), "\u{1B}[38;2;255;255;0mLine\u{1B}[38;2;100;100;100m(\u{1B}[38;2;50;120;255m1\u{1B}[38;2;100;100;100m, \u{1B}[38;2;50;120;255m-2\u{1B}[38;2;100;100;100m)\u{1B}[m");
+		assert_eq!(format!("{:?}", Colored::new(&Shape::Dot)), "\u{1B}[38;2;255;255;0mDot\u{1B}[m");
+		assert_eq!(format!("{:?}", Colored::new(&Custom(true))), "\u{1B}[38;2;255;255;0mCustom\u{1B}[38;2;100;100;100m(\u{1B}[38;2;50;120;255mtrue\u{1B}[38;2;100;100;100m)\u{1B}[m");
+		// Nested derived types are colored once, by the outer type.
+		assert_eq!(format!("{:?}", Colored::new(&Point { x: 1, r#name: "a" })), "\u{1B}[38;2;255;255;0mPoint \u{1B}[38;2;100;100;100m{ \u{1B}[38;2;0;255;255mx\u{1B}[38;2;100;100;100m: \u{1B}[38;2;50;120;255m1\u{1B}[38;2;100;100;100m, \u{1B}[38;2;0;255;255mname\u{1B}[38;2;100;100;100m: \u{1B}[38;2;0;255;0m\"a\" \u{1B}[38;2;100;100;100m}\u{1B}[m");
+		assert_eq!(format!("{:?}", Colored::new(&Point { x: Shape::Dot, r#name: "" })).matches("\u{1B}[38;2;255;255;0m").count(), 2);
+		assert_eq!(Colored::new(&User { name: "Ecconia", id: 7, width: 3 }).to_string(), "\u{1B}[38;2;255;255;0mEcconia\u{1B}[m (#  7)");
+		assert_eq!(Colored::new(&Status::Online).to_string(), "\u{1B}[m\u{1B}[38;2;0;255;0mOnline\u{1B}[m");
+		assert_eq!(Colored::new(&Status::Away(5)).to_string(), "\u{1B}[mAway for \u{1B}[38;2;0;255;255m5\u{1B}[m minutes");
+		assert_eq!(Colored::new(&Status::Error { code: 404, reason: "gone".into() }).to_string(), "\u{1B}[38;2;255;0;0m\"gone\"\u{1B}[m \u{1B}[38;2;255;255;0m0x194\u{1B}[m");
+		assert_eq!(Colored::new(&Pair(true, "Hi")).to_string(), "\u{1B}[38;2;100;200;255mHi{}\u{1B}[m true");
+		assert_eq!(format!("{:?}", Colored::new(&Renamed(1))), "\u{1B}[38;2;255;255;0mRenamed\u{1B}[38;2;100;100;100m(\u{1B}[38;2;50;120;255m1\u{1B}[38;2;100;100;100m)\u{1B}[m");
+		assert_eq!(Colored::new(&RenamedDisplay(2)).to_string(), "\u{1B}[38;2;0;255;0m2\u{1B}[m");
+		// Without Colored the output stays plain while colors are enabled, but Colored values nested in it are colored:
+		assert_eq!(format!("{:?}", Shape::Line(1, -2)), "Line(1, -2)");
+		assert_eq!(User { name: "Ecconia", id: 7, width: 3 }.to_string(), "Ecconia (#  7)");
+		assert_eq!(format!("{:?}", [Colored::new(&Shape::Dot)]), "[\u{1B}[38;2;255;255;0mDot\u{1B}[m]");
+		// A panic while formatting must not leave colors requested or disabled:
+		assert!(panic::catch_unwind(|| format!("{:?}", Colored::new(&Point { x: Panicking, r#name: "" }))).is_err());
+		assert_eq!(format!("{:?}", Shape::Dot), "Dot");
+		assert_eq!(format!("{:?}", Colored::new(&Shape::Dot)), "\u{1B}[38;2;255;255;0mDot\u{1B}[m");
+		
+		set_theme_override(Some(Theme::Light));
+		assert_eq!(Colored::new(&Status::Online).to_string(), "\u{1B}[m\u{1B}[38;2;0;150;0mOnline\u{1B}[m");
+		assert_eq!(ansi_format!("«lw»{}", name), "\u{1B}[38;2;0;0;0mEcconia");
+		set_theme_override(Some(Theme::Dark));
+		assert_eq!(ansi_format!("«lw»{}", name), "\u{1B}[38;2;255;255;255mEcconia");
+		set_theme_override(None);
+		
+		set_terminal_caps_override(Some(TerminalCaps { depth: ColorDepth::Colors256, ..truecolor }));
+		assert_eq!(ansi_format!("«r»{}«»", name), "\u{1B}[38;5;196mEcconia\u{1B}[m");
+		set_terminal_caps_override(Some(TerminalCaps { depth: ColorDepth::Colors8, ..truecolor }));
+		assert_eq!(ansi_format!(["g"] "{}", name), "\u{1B}[32mEcconia\u{1B}[m");
+		// Italics and links are removed, when the terminal does not support them:
+		set_terminal_caps_override(Some(TerminalCaps { italic: false, hyperlinks: false, ..truecolor }));
+		assert_eq!(ansi_format!("«i,r»{}«» «link:https://example.com»{}«/link»", name, name), "\u{1B}[38;2;255;0;0mEcconia\u{1B}[m Ecconia");
+		set_terminal_caps_override(None);
+		
+		set_color_override(Some(false));
+		assert_eq!(ansi_format!("«r»{}«» ««»", name), "Ecconia «»");
+		assert_eq!(ansi_format!(["c", "w"] "Hi {}{}", name, "!"), "Hi Ecconia!");
+		assert_eq!(team_format!("«custom»{}«»", name), "Ecconia");
+		assert_eq!(format!("{:?}", Colored::new(&Shape::Line(1, -2))), "Line(1, -2)");
+		assert_eq!(Colored::new(&User { name: "Ecconia", id: 7, width: 3 }).to_string(), "Ecconia (#  7)");
+		assert_eq!(Colored::new(&Status::Error { code: 404, reason: "gone".into() }).to_string(), "\"gone\" 0x194");
+		
+		set_color_override(None);
+	}
+}
+
+
+#[cfg(test)]
+mod debug_derive {
+	use ecc_ansi_lib::AnsiDebug;
+	
+	#[derive(AnsiDebug)]
+	pub(crate) struct Point<T> {
+		pub(crate) x: T,
+		pub(crate) r#name: &'static str,
+	}
+	
+	#[derive(AnsiDebug)]
+	pub(crate) enum Shape {
+		Dot,
+		Line(u8, i8),
+		Named { id: char },
+		// Fields must not shadow the formatter of the generated code.
+		Options { formatter: u8, r#type: bool },
+	}
+	
+	// Shift operators in discriminants are not generics.
+	#[derive(AnsiDebug)]
+	enum Flags {
+		A = 1 << 2,
+		B = 3,
+		C = 64 >> 2,
+	}
+	
+	#[derive(AnsiDebug)]
+	#[ansi_debug(field = "custom", palette = { @default custom 100 200 255 })]
+	pub(crate) struct Custom(pub(crate) bool);
+	
+	// The crate path can be changed, for renamed or re-exported crates.
+	pub(crate) mod reexport {
+		pub use ecc_ansi_lib as inner;
+	}
+	
+	#[derive(AnsiDebug)]
+	#[ansi_debug(crate = self::reexport::inner)]
+	pub(crate) struct Renamed(pub(crate) u8);
+	
+	#[derive(AnsiDebug)]
+	pub(crate) struct Wrapper<'a, T: Clone, const N: usize = 2>(&'a [T; N], Option<fn() -> T>) where T: PartialEq;
+	
+	// Without Colored the output is plain and has to match #[derive(Debug)], like in format!(), logs and assertion messages.
+	#[test]
+	fn plain() {
+		assert_eq!(format!("{:?}", Wrapper(&[1.5, -2.0], None)), "Wrapper([1.5, -2.0], None)");
+		let point = Point { x: Shape::Named { id: '"' }, r#name: "\"Ecc\"" };
+		assert_eq!(format!("{point:?}"), r#"Point { x: Named { id: '"' }, name: "\"Ecc\"" }"#);
+		assert_eq!(format!("{:#?}", Point { x: [Shape::Dot], r#name: "" }), "Point {\n    x: [\n        Dot,\n    ],\n    name: \"\",\n}");
+		assert_eq!(format!("{:?}", [Flags::A, Flags::B, Flags::C]), "[A, B, C]");
+		assert_eq!(format!("{:?}", Shape::Options { formatter: 7, r#type: true }), "Options { formatter: 7, type: true }");
+		assert_eq!(format!("{:?}", Shape::Line(1, -2)), "Line(1, -2)");
+		assert_eq!(format!("{:?}", Custom(true)), "Custom(true)");
+		assert_eq!(format!("{:?}", Renamed(1)), "Renamed(1)");
+	}
+}
+
+#[cfg(test)]
+mod display_derive {
+	use super::debug_derive::reexport;
+	use ecc_ansi_lib::AnsiDisplay;
+	
+	#[derive(AnsiDisplay)]
+	#[ansi(fmt = "«y»{name}«» (#{id:>width$})")]
+	pub(crate) struct User {
+		pub(crate) name: &'static str,
+		pub(crate) id: u32,
+		pub(crate) width: usize,
+	}
+	
+	#[derive(AnsiDisplay)]
+	#[ansi(highlight = "c")]
+	pub(crate) enum Status {
+		#[ansi(fmt = "«g»Online«»")]
+		Online,
+		#[ansi(fmt = "Away for {0} minutes")]
+		Away(u32),
+		#[ansi(fmt = "{reason:?} {code:#x}", highlight = "r", number = "y")]
+		Error { code: u16, reason: String },
+	}
+	
+	#[derive(AnsiDisplay)]
+	#[ansi(fmt = "«custom»{1}{{}}«» {0}", palette = { custom 100 200 255 })]
+	pub(crate) struct Pair(pub(crate) bool, pub(crate) &'static str);
+	
+	#[derive(AnsiDisplay)]
+	#[ansi(fmt = "«g»{0}«»", crate = reexport::inner)]
+	pub(crate) struct RenamedDisplay(pub(crate) u8);
+	
+	// Without Colored the output is plain, like in format!(), logs and assertion messages.
+	#[test]
+	fn plain() {
+		assert_eq!(User { name: "Ecconia", id: 7, width: 3 }.to_string(), "Ecconia (#  7)");
+		assert_eq!(Status::Online.to_string(), "Online");
+		assert_eq!(Status::Away(5).to_string(), "Away for 5 minutes");
+		assert_eq!(Status::Error { code: 404, reason: "gone".into() }.to_string(), "\"gone\" 0x194");
+		assert_eq!(Pair(true, "Hi").to_string(), "Hi{} true");
+		assert_eq!(RenamedDisplay(2).to_string(), "2");
+	}
+}
+
+#[cfg(test)]
+mod palette_file {
+	use ecc_ansi_lib::{ansi_palette_file, render, Palette};
+	use std::fs;
+	
+	ansi_palette_file!(team_ansi, "tests/colors.palette");
+	
+	#[test]
+	fn defined_macro() {
+		assert_eq!(team_ansi!("«warning»Careful!«»"), "\u{1B}[38;2;255;200;0mCareful!\u{1B}[m");
+		assert_eq!(team_ansi!("«r on calm»"), "\u{1B}[38;2;255;0;0;48;2;90;163;237m");
+		// The defined macro can be extended like ansi_extend!():
+		assert_eq!(team_ansi!("«warning»", @override warning 0 0 0), "\u{1B}[38;2;0;0;0m");
+		assert_eq!(team_ansi!("«warning»", @depth 256), "\u{1B}[38;5;220m");
+	}
+	
+	// The command-line tool parses palette files at runtime, the results must match.
+	#[test]
+	fn runtime_palette() {
+		let palette = Palette::parse(&fs::read_to_string("tests/colors.palette").unwrap()).unwrap();
+		assert_eq!(team_ansi!("«accent»Calm«»"), "\u{1B}[38;2;90;163;237mCalm\u{1B}[m");
+		assert_eq!(render("«accent»Calm«»", &palette).unwrap(), team_ansi!("«accent»Calm«»"));
+		assert_eq!(render("«warning on calm»Careful«» «i,g»", &palette).unwrap(), team_ansi!("«warning on calm»Careful«» «i,g»"));
+	}
+}
+
+#[cfg(test)]
+mod text {
+	use ecc_ansi_lib::{ansi, ansi_to_html, ansi_to_markup, ansi_to_svg, arg_wrapper, hyperlink, render, strip_ansi, visible_width, Palette, SvgOptions};
+	use std::borrow::Cow;
+	
+	#[test]
+	fn strip() {
+		assert!(matches!(strip_ansi("Plain text"), Cow::Borrowed("Plain text")));
+		assert_eq!(strip_ansi(ansi!("«r»Red«» «b,lo on db»Mixed«/b»!")), "Red Mixed!");
+		assert_eq!(strip_ansi(&format!(arg_wrapper!("Hi {}!", "c", "w"), "Ecconia")), "Hi Ecconia!");
+		// Hyperlinks (OSC 8), with both terminators:
+		assert_eq!(strip_ansi("\u{1B}]8;;https://example.com\u{1B}\\Link\u{1B}]8;;\u{07}!"), "Link!");
+		// Other sequences, like cursor movement, character sets and 8-bit CSI:
+		assert_eq!(strip_ansi("a\u{1B}[2Kb\u{1B}(Bc\u{1B}7d\u{9B}1me"), "abcde");
+		// Unterminated sequences are removed up to the end:
+		assert_eq!(strip_ansi("Text\u{1B}[38;2"), "Text");
+	}
+	
+	#[test]
+	fn width() {
+		assert_eq!(visible_width(ansi!("«r»Red«»")), 3);
+		assert_eq!(visible_width("日本語"), 6);
+		assert_eq!(visible_width("e\u{301}"), 1);
+		assert_eq!(visible_width("\u{1F600}!"), 3);
+		// Joined emoji, flags and skin tones:
+		assert_eq!(visible_width("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"), 2);
+		assert_eq!(visible_width("\u{1F1E9}\u{1F1EA}\u{1F1EB}\u{1F1F7}"), 4);
+		assert_eq!(visible_width("\u{1F44D}\u{1F3FD}"), 2);
+		assert_eq!(visible_width("a\u{200B}b\tc"), 3);
+	}
+	
+	#[test]
+	fn link() {
+		let link = hyperlink("https://example.com", "Example");
+		assert_eq!(link, "\u{1B}]8;;https://example.com\u{07}Example\u{1B}]8;;\u{07}");
+		assert_eq!(strip_ansi(&link), "Example");
+		assert_eq!(visible_width(&link), 7);
+		// Whitespaces and control characters cannot end the escape sequence:
+		let link = hyperlink("https://example.com/a b\u{7}\u{1B}[31m\u{85}", "Example");
+		assert_eq!(link, "\u{1B}]8;;https://example.com/a%20b%07%1B[31m%C2%85\u{07}Example\u{1B}]8;;\u{07}");
+		assert_eq!(strip_ansi(&link), "Example");
+	}
+	
+	#[test]
+	fn html() {
+		assert_eq!(ansi_to_html("Plain <b> & 'text'"), "Plain &lt;b&gt; &amp; &#39;text&#39;");
+		// Adjacent text of the same style is merged, sequences without text in between produce no span:
+		assert_eq!(ansi_to_html(ansi!("«r»Red«r» more«»«g»«» and «w»white")), "<span style=\"color:#ff0000\">Red more</span> and <span style=\"color:#dcdcdc\">white</span>");
+		assert_eq!(ansi_to_html(ansi!("«b,i,u,strike,d»All«/u»«/strike»«/b»«/i»")), "<span style=\"font-weight:bold;opacity:0.5;font-style:italic;text-decoration:underline line-through\">All</span>");
+		assert_eq!(ansi_to_html(ansi!("«ly on db»Both«/fg» bg«/bg» none")), "<span style=\"color:#ffff78;background-color:#000096\">Both</span><span style=\"background-color:#000096\"> bg</span> none");
+		// Reversed colors fall back to the colors of a dark terminal:
+		assert_eq!(ansi_to_html(ansi!("«rev»Reverse")), "<span style=\"color:#000000;background-color:#e5e5e5\">Reverse</span>");
+		// The 256 and 16 color palettes:
+		assert_eq!(ansi_to_html("\u{1B}[38;5;196ma\u{1B}[38;5;244mb\u{1B}[91;42mc\u{1B}[0md"), "<span style=\"color:#ff0000\">a</span><span style=\"color:#808080\">b</span><span style=\"color:#ff0000;background-color:#00cd00\">c</span>d");
+		// Links keep the style of their text:
+		let link = format!("See {}!", hyperlink("https://example.com/?a=1&b=2", ansi!("«c»here«»")));
+		assert_eq!(ansi_to_html(&link), "See <a href=\"https://example.com/?a=1&amp;b=2\"><span style=\"color:#00ffff\">here</span></a>!");
+		// Links with schemes other than http, https and mailto could run scripts, only their text is kept:
+		assert_eq!(ansi_to_html("\u{1B}]8;;javascript:alert(1)\u{07}click\u{1B}]8;;\u{07}"), "click");
+		assert_eq!(ansi_to_html(&format!("{}{}", hyperlink("JavaScript:alert(1)", "a"), hyperlink("mailto:a@b.c", "b"))), "a<a href=\"mailto:a@b.c\">b</a>");
+	}
+	
+	#[test]
+	fn markup() {
+		let palette = Palette::default();
+		let markup = |text: &str| ansi_to_markup(text, &palette);
+		assert_eq!(markup(ansi!("«r»Red«» plain")), "«r»Red«» plain");
+		assert_eq!(render(&markup(ansi!("«r»Red«» plain")), &palette).unwrap(), ansi!("«r»Red«» plain"));
+		assert_eq!(markup(ansi!("«b,lo»Mixed«/b»!")), "«b,lo»Mixed«/b»!");
+		// Single item codes prefer colors, thus attributes use their full name. Colors named like attributes are forced in other codes:
+		assert_eq!(markup(ansi!("«b»Blue «bold»bold «i»both")), "«b»Blue «bold»bold «italic»both");
+		assert_eq!(markup(ansi!("«fg:b,u»Blue«/u,r»red«/fg»")), "«u,fg:b»Blue«/u,r»red«»");
+		// Bold and dim are disabled together:
+		assert_eq!(markup("\u{1B}[1;2mBoth\u{1B}[22;1mBold"), "«b,d»Both«/b,b»Bold");
+		// Colors missing in the palette, backgrounds and 256 color sequences:
+		assert_eq!(markup(ansi!("«1,2,3»Custom «ly on db»both«/bg» «»")), "«1,2,3»Custom «ly,bg:db»both«/bg» «»");
+		assert_eq!(markup("\u{1B}[38;5;196mRed\u{1B}[0m"), "«r»Red«»");
+		// Escaped openers and links:
+		assert_eq!(markup(ansi!("Use ««r» for red")), "Use ««r» for red");
+		assert_eq!(markup(&hyperlink("https://example.com", ansi!("«c»Link«»"))), "«link:https://example.com»«c»Link«/link»«»");
+		assert_eq!(markup("\u{1B}]8;;https://a.b/x»\u{07}y\u{1B}]8;;\u{07}"), "«link:https://a.b/x%C2%BB»y«/link»");
+		assert_eq!(render("«link:https://a.b/x%C2%BB»y«/link»", &palette).unwrap(), "\u{1B}]8;;https://a.b/x%C2%BB\u{07}y\u{1B}]8;;\u{07}");
+	}
+	
+	#[test]
+	fn svg() {
+		let options = SvgOptions { font_size: 10.0, ..SvgOptions::default() };
+		// Cells are 6 by 12 pixels, with 10 pixels padding:
+		let svg = ansi_to_svg(ansi!("«r on db»Red«» <&>\n日本\tEnd\n"), &options);
+		assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"86\" height=\"44\" viewBox=\"0 0 86 44\">\n<rect width=\"100%\" height=\"100%\" fill=\"#000000\"/>\n"));
+		assert!(svg.contains("<rect x=\"10\" y=\"10\" width=\"18\" height=\"12\" fill=\"#000096\"/>\n<text x=\"10\" y=\"19.6\" textLength=\"18\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#ff0000\">Red</text>\n"));
+		assert!(svg.contains("<text x=\"28\" y=\"19.6\" textLength=\"24\" lengthAdjust=\"spacingAndGlyphs\"> &lt;&amp;&gt;</text>\n"));
+		// Wide characters take two cells, tabs advance to the next multiple of 8 columns:
+		assert!(svg.contains("<text x=\"10\" y=\"31.6\" textLength=\"66\" lengthAdjust=\"spacingAndGlyphs\">日本    End</text>\n"));
+		assert!(svg.ends_with("</g>\n</svg>\n"));
+		assert!(!svg.contains("<circle"));
+		
+		let window = ansi_to_svg("Text", &SvgOptions { window: true, title: "Demo <1>".to_string(), ..options });
+		assert_eq!(window.matches("<circle").count(), 3);
+		assert!(window.contains(">Demo &lt;1&gt;</text>"));
+		assert!(window.contains("<text x=\"10\" y=\"39.6\" textLength=\"24\""));
+	}
+}
+
+#[cfg(test)]
+mod terminal {
+	use ecc_ansi_lib::{ansi, ansi_extend, hyperlink, ColorDepth, TerminalCaps};
+	use std::borrow::Cow;
+	
+	/// Detects the capabilities with only the provided environment variables, terminfo entries are taken from 'tests/terminfo'.
+	fn detect(variables: &[(&str, &str)]) -> TerminalCaps {
+		let terminfo = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/terminfo");
+		TerminalCaps::from_environment(|name| {
+			if name == "TERMINFO" {
+				return Some(terminfo.to_string());
+			}
+			variables.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+		})
+	}
+	
+	#[test]
+	fn environment() {
+		assert_eq!(detect(&[]), TerminalCaps { depth: ColorDepth::Colors8, italic: false, hyperlinks: false });
+		assert_eq!(detect(&[("TERM", "unknown"), ("COLORTERM", "truecolor")]).depth, ColorDepth::TrueColor);
+		assert_eq!(detect(&[("TERM", "unknown")]).depth, ColorDepth::Colors16);
+		assert_eq!(detect(&[("TERM", "screen-256color")]).depth, ColorDepth::Colors256);
+		assert_eq!(detect(&[("TERM", "xterm-kitty")]), TerminalCaps { depth: ColorDepth::TrueColor, italic: true, hyperlinks: true });
+		assert!(detect(&[("TERM_PROGRAM", "WezTerm")]).hyperlinks);
+		assert!(detect(&[("TERM", "unknown"), ("VTE_VERSION", "7600")]).hyperlinks);
+	}
+	
+	#[test]
+	fn terminfo() {
+		// Uses the extended number format (colors#0x1000000) and the extended boolean 'RGB'.
+		assert_eq!(detect(&[("TERM", "test-direct")]), TerminalCaps { depth: ColorDepth::TrueColor, italic: true, hyperlinks: false });
+		assert_eq!(detect(&[("TERM", "test-256")]), TerminalCaps { depth: ColorDepth::Colors256, italic: false, hyperlinks: false });
+		assert_eq!(detect(&[("TERM", "test-8")]).depth, ColorDepth::Colors8);
+	}
+	
+	#[test]
+	fn reduce() {
+		let full = TerminalCaps { depth: ColorDepth::TrueColor, italic: true, hyperlinks: true };
+		let link = format!("{}!", hyperlink("https://example.com", ansi!("«i»Link«»")));
+		assert!(matches!(full.reduce(&link), Cow::Borrowed(_)));
+		// Italic parameters are removed, sequences with only italic parameters are dropped (but not resets):
+		let no_italic = TerminalCaps { italic: false, ..full };
+		assert_eq!(no_italic.reduce(ansi!("«i,b,r»A«/i»B«i»C«»")), "\u{1B}[1;38;2;255;0;0mABC\u{1B}[m");
+		assert_eq!(no_italic.reduce("\u{1B}[38;2;3;23;3;3mA\u{1B}[23m"), "\u{1B}[38;2;3;23;3mA");
+		assert_eq!(no_italic.reduce(&link), "\u{1B}]8;;https://example.com\u{07}Link\u{1B}[m\u{1B}]8;;\u{07}!");
+		// Links keep their text:
+		let no_links = TerminalCaps { hyperlinks: false, ..full };
+		assert_eq!(no_links.reduce(&link), "\u{1B}[3mLink\u{1B}[m!");
+		assert_eq!(no_links.reduce("\u{1B}]8;;https://example.com\u{1B}\\Link\u{1B}]8;;\u{1B}\\ \u{1B}]0;Title\u{07}"), "Link \u{1B}]0;Title\u{07}");
+		
+		// Colors are mapped like with '@depth' at compile time:
+		let depth = |depth| TerminalCaps { depth, ..full };
+		assert_eq!(depth(ColorDepth::Colors256).reduce(ansi!("«r,i on lb»A«grad:y..c»Bc«/grad»")), ansi_extend!("«r,i on lb»A«grad:y..c»Bc«/grad»", @depth 256));
+		assert_eq!(depth(ColorDepth::Colors16).reduce(ansi!("«o on db»A«» «lg»B")), ansi_extend!("«o on db»A«» «lg»B", @depth 16));
+		assert_eq!(depth(ColorDepth::Colors8).reduce(ansi!("«o on db»A«» «lg»B")), ansi_extend!("«o on db»A«» «lg»B", @depth 8));
+		assert_eq!(depth(ColorDepth::Colors8).reduce("\u{1B}[38;5;196;92;104mA"), "\u{1B}[31;32;44mA");
+		// Malformed extended colors are kept:
+		assert_eq!(depth(ColorDepth::Colors16).reduce("\u{1B}[38;2;300m\u{1B}[48;5m"), "\u{1B}[38;2;300m\u{1B}[48;5m");
+	}
+}
+
+#[cfg(test)]
+mod testing {
+	use ecc_ansi_lib::{ansi, ansi_extend, arg_wrapper, assert_ansi_snapshot, Palette};
+	use std::panic;
+	
+	#[test]
+	fn snapshot_file() {
+		assert_ansi_snapshot!("introduction", ansi!("«r»Red«» text\n«b,lo»Mixed«/b» «1,2,3»custom«»"));
+		assert_ansi_snapshot!(format!(arg_wrapper!("Hello {}!", "c", "w"), "Ecconia"), @"«w»Hello «c»Ecconia«w»!«»");
+	}
+	
+	#[test]
+	fn palette_names() {
+		let palette = Palette::parse("@default warn 255 100 10").unwrap();
+		assert_ansi_snapshot!("palette", ansi_extend!("«warn»Careful«» «r»not«»", warn 255 100 10), &palette);
+	}
+	
+	#[test]
+	fn mismatch() {
+		let error = panic::catch_unwind(|| {
+			assert_ansi_snapshot!(ansi!("First\n«r»Second«»\nThird"), @"First\n«g»Second«»\nThird");
+		}).unwrap_err();
+		let message = error.downcast_ref::<String>().unwrap();
+		assert_eq!(message, "Output does not match the expected markup (- expected, + actual):\n First\n-«g»Second«»\n+«r»Second«»\n Third\n");
+	}
+}
+
+#[cfg(test)]
+mod cli {
+	use std::io::Write;
+	use std::process::{Command, Stdio};
+	
+	/// Runs the command-line tool with colors forced in a truecolor terminal, returns whether it succeeded and its output (stdout or stderr).
+	fn run(arguments: &[&str], stdin: Option<&str>) -> (bool, String) {
+		run_in(&[("COLORTERM", "truecolor")], arguments, stdin)
+	}
+	
+	/// Runs the command-line tool with colors forced and only the provided environment variables.
+	fn run_in(variables: &[(&str, &str)], arguments: &[&str], stdin: Option<&str>) -> (bool, String) {
+		let mut child = Command::new(env!("CARGO_BIN_EXE_ecc_ansi"))
+			.args(arguments)
+			.env_clear()
+			.env("CLICOLOR_FORCE", "1")
+			.envs(variables.iter().copied())
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::piped())
+			.spawn()
+			.unwrap();
+		child.stdin.take().unwrap().write_all(stdin.unwrap_or_default().as_bytes()).unwrap();
+		let output = child.wait_with_output().unwrap();
+		let text = if output.status.success() { output.stdout } else { output.stderr };
+		(output.status.success(), String::from_utf8(text).unwrap())
+	}
+	
+	#[test]
+	fn render() {
+		assert_eq!(run(&["«r»Error:«»", "failed"], None), (true, "\u{1B}[38;2;255;0;0mError:\u{1B}[m failed\n".to_string()));
+		// A reset is added, when the markup does not end with one:
+		assert_eq!(run(&["«r»Red"], None), (true, "\u{1B}[38;2;255;0;0mRed\u{1B}[m\n".to_string()));
+		assert_eq!(run(&["--", "--r»"], None), (true, "--r»\n".to_string()));
+		// Without arguments stdin is rendered as is:
+		assert_eq!(run(&[], Some("«g»Line«»\nNext")), (true, "\u{1B}[38;2;0;255;0mLine\u{1B}[m\nNext".to_string()));
+	}
+	
+	#[test]
+	fn options() {
+		assert_eq!(run(&["--depth", "256", "«r»Red«»"], None), (true, "\u{1B}[38;5;196mRed\u{1B}[m\n".to_string()));
+		// The depth option replaces the detected depth, also when it is higher:
+		assert_eq!(run_in(&[], &["--depth", "256", "«o»x«»"], None), (true, "\u{1B}[38;5;202mx\u{1B}[m\n".to_string()));
+		assert_eq!(run_in(&[("TERM", "xterm")], &["--depth", "truecolor", "«o»x«»"], None), (true, "\u{1B}[38;2;255;100;0mx\u{1B}[m\n".to_string()));
+		assert_eq!(run_in(&[], &["«o»x«»"], None), (true, "\u{1B}[31mx\u{1B}[m\n".to_string()));
+		assert_eq!(run(&["--no-color", "«r»Red«»"], None), (true, "Red\n".to_string()));
+		assert_eq!(run(&["--palette", "tests/colors.palette", "--depth", "256"], Some("«warning»Careful«»")), (true, "\u{1B}[38;5;220mCareful\u{1B}[m".to_string()));
+		assert_eq!(run(&["--strip"], Some("\u{1B}[31mRed\u{1B}[m «r»")), (true, "Red «r»".to_string()));
+		assert!(run(&["--help"], None).1.starts_with("Usage: ecc_ansi [OPTIONS] [MARKUP]..."));
+	}
+	
+	#[test]
+	fn errors() {
+		assert_eq!(run(&["«nope»"], None), (false, "ecc_ansi: Could not parse ANSI color format: 'nope' (in 'nope').\n".to_string()));
+		assert_eq!(run(&["--depth", "5", "Text"], None), (false, "ecc_ansi: Unknown color depth '5', expected one of: truecolor, 256, 16, 8\n".to_string()));
+		assert_eq!(run(&["--colour"], None), (false, "ecc_ansi: Unknown option '--colour', see '--help'\n".to_string()));
+		assert!(run(&["--palette", "missing.palette", "Text"], None).1.starts_with("ecc_ansi: Could not read palette file 'missing.palette': "));
+	}
 }